@@ -0,0 +1,549 @@
+use crate::creature::command::{Command, NUM_COMMANDS};
+use crate::events::{Event, NUM_EVENT_KINDS};
+use crate::parsing;
+use crate::world::{Terrain, NUM_TERRAINS};
+
+use std::fs;
+
+#[derive(Clone, Copy)]
+pub enum ActionModel {
+    // Actions pick a direction at random using the creature's direction weights
+    Random,
+    // Actions target the tile the creature is facing and sensing only sees that tile
+    Directed,
+}
+
+#[derive(Clone, Copy)]
+pub enum VisionShape {
+    // Only the tiles in a straight line
+    Line,
+    // Every tile of the quarter of the view centered on the direction
+    Quadrant,
+}
+
+#[derive(Clone, Copy)]
+pub enum WorldGenerator {
+    // Every tile starts with the same food
+    Flat,
+    // Fertility follows smooth noise
+    Noise,
+    // Fertile patches surrounded by barren land
+    Islands,
+    // Alternating fertile and barren vertical bands
+    Stripes,
+    // A single fertile area fading into barren land
+    Oasis,
+}
+
+#[derive(Clone, Copy)]
+pub enum SpawnStrategy {
+    // Anywhere in the world
+    Uniform,
+    // In groups sharing a genome
+    Clustered,
+    // Each genome in its own vertical band of the world
+    Regions,
+    // Following the blue of the layout
+    Image,
+}
+
+#[derive(Clone, Copy)]
+pub struct TerrainConfig {
+    // Most food a tile can hold at the end of a round, 0 means no limit
+    pub max_food: u32,
+    // Food a tile can hold without any of it spoiling
+    pub spoil_threshold: u32,
+    // Percentage of the food above the threshold that spoils every round
+    pub spoil_rate: u32,
+}
+
+#[derive(Clone)]
+pub struct Config {
+    pub width: u32,
+    pub height: u32,
+    pub num_creatures: u32,
+
+    // Energy charged every time a gene with the given command is executed
+    pub command_costs: [u32; NUM_COMMANDS as usize],
+    // Energy charged every round for each gene in the genome
+    pub gene_cost: u32,
+
+    // Rounds a creature lives for, 0 means creatures only die of starvation or predation
+    pub lifespan: u32,
+    // Maximum change of lifespan from parent to child
+    pub lifespan_mutation: u32,
+    // Age a creature must reach before it can reproduce
+    pub maturity_age: u32,
+    // Extra energy loss reached at the end of the lifespan, it grows linearly with age
+    pub senescence_loss: u32,
+
+    // Maximum genome edit distance to the representative of a species to belong to it
+    pub species_threshold: usize,
+    // Number of rounds between two species clusterings
+    pub species_interval: u32,
+
+    // Percentage of a killed creature's energy left as a carcass instead of going to its killer
+    pub kill_carcass_ratio: u32,
+    // Carcass energy turned into food on each tile every round
+    pub carcass_decay: u32,
+
+    // Initial combat traits
+    pub strength: u32,
+    pub size: u32,
+    pub armour: u32,
+    // Maximum change of each combat trait from parent to child
+    pub combat_mutation: u32,
+    // Extra energy loss per round in percent of the sum of the combat traits
+    pub combat_upkeep: u32,
+
+    pub action_model: ActionModel,
+
+    // Initial vision range of the creatures
+    pub vision: u32,
+    pub vision_mutation: u32,
+    pub max_vision: u32,
+    // Extra energy loss per round for each tile of vision past the first
+    pub vision_cost: u32,
+    pub vision_shape: VisionShape,
+
+    pub pheromone_layers: usize,
+    // Amount of pheromone deposited by Emit
+    pub pheromone_emit: u32,
+    // Percentage of pheromone that disappears every round
+    pub pheromone_evaporation: u32,
+    // Percentage of pheromone that spreads to the neighbouring tiles every round
+    pub pheromone_diffusion: u32,
+    // Chance in percent for a child to emit and smell a random layer instead of its parent's
+    pub pheromone_mutation: u32,
+
+    // Percentage of its energy a creature gives away with Share
+    pub share_ratio: u32,
+    // Only share with creatures whose genome is within kin_threshold edits
+    pub share_kin_only: bool,
+    pub kin_threshold: usize,
+
+    // Initial number of rounds an egg takes to hatch, 0 disables eggs
+    pub incubation: u32,
+    pub incubation_mutation: u32,
+
+    // Percentage of the energy loss and of the defense kept while asleep
+    pub sleep_metabolism: u32,
+    pub sleep_defense: u32,
+
+    // Regrowth added to a tile by Plant and the highest regrowth a tile can have
+    pub plant_regrowth: u32,
+    pub max_regrowth: u32,
+
+    pub terrains: [TerrainConfig; NUM_TERRAINS],
+
+    // Percentage of the initial creatures carrying the disease
+    pub disease_initial: u32,
+    // Chance in percent to pass the disease to each neighbour every round
+    pub disease_transmission: u32,
+    // Extra energy loss per round while infected
+    pub disease_drain: u32,
+    // Rounds before recovering, 0 means the disease is never cured
+    pub disease_duration: u32,
+    // Initial immunity of the creatures in percent
+    pub immunity: u32,
+    pub immunity_mutation: u32,
+    // Extra energy loss per round in percent of the immunity
+    pub immunity_cost: u32,
+
+    // Events triggered at a given round
+    pub events: Vec<(u32, Event)>,
+    // Chance in percent for each kind of event to happen somewhere every round
+    pub event_chances: [f32; NUM_EVENT_KINDS],
+    // Radius of the random events
+    pub event_radius: u32,
+    // File of interventions run during the simulation
+    pub timeline: Option<String>,
+
+    // Temperature at the top and bottom edges and in the middle of the world
+    pub temperature_pole: f32,
+    pub temperature_equator: f32,
+    // Maximum random offset of each tile's temperature
+    pub temperature_noise: f32,
+    // Seasons move every temperature by up to the amplitude over their length in rounds
+    pub season_amplitude: f32,
+    pub season_length: u32,
+    // Initial temperature creatures are best suited to and its maximum change between a parent and its child
+    pub thermal_optimum: f32,
+    pub thermal_mutation: f32,
+    // Extra energy loss per round for each degree away from the thermal optimum
+    pub thermal_cost: f32,
+
+    // Fibre every tile starts with, gains every round and can't grow past
+    pub fibre_initial: u32,
+    pub fibre_regrowth: u32,
+    pub fibre_max: u32,
+    // Initial percentage of the energy extracted from fibre rather than food
+    // and its maximum change between a parent and its child
+    pub fibre_digestion: u32,
+    pub digestion_mutation: u32,
+
+    // Creatures within that radius count as neighbours
+    pub crowding_radius: u32,
+    // Extra energy loss per round for each neighbour above the threshold
+    pub crowding_threshold: u32,
+    pub crowding_cost: u32,
+    // Creatures with at least that many neighbours can't reproduce, 0 means no limit
+    pub crowding_reproduction: u32,
+    // Creatures and eggs past which no more children are made, 0 means no limit
+    pub max_population: u32,
+
+    pub world_generator: WorldGenerator,
+    // Seed of the generator and of the temperature noise, a random one is picked if none is given
+    pub world_seed: Option<u64>,
+    // Size in tiles of the generated features
+    pub generator_scale: u32,
+    // Food and regrowth of the most fertile tiles
+    pub generator_food: u32,
+    pub generator_regrowth: u32,
+    // PPM image the world is read from, overrides the size and the generated food and terrain
+    pub layout: Option<String>,
+    // Text file describing the world and its creatures, overrides the size and the initial creatures
+    pub scenario: Option<String>,
+
+    pub spawn_strategy: SpawnStrategy,
+    // Number of groups and their radius with the clustered strategy
+    pub spawn_clusters: u32,
+    pub spawn_cluster_radius: u32,
+    // File of the genomes given to the initial creatures
+    pub genome_bank: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        let mut command_costs = [0; NUM_COMMANDS as usize];
+        command_costs[Command::Plant as usize] = 20;
+        command_costs[Command::Build as usize] = 50;
+        command_costs[Command::Dig as usize] = 50;
+
+        Config {
+            width: 800,
+            height: 600,
+            num_creatures: 500,
+            command_costs,
+            gene_cost: 0,
+            lifespan: 0,
+            lifespan_mutation: 0,
+            maturity_age: 0,
+            senescence_loss: 0,
+            species_threshold: 2,
+            species_interval: 50,
+            kill_carcass_ratio: 50,
+            carcass_decay: 1,
+            strength: 10,
+            size: 10,
+            armour: 0,
+            combat_mutation: 1,
+            combat_upkeep: 0,
+            action_model: ActionModel::Random,
+            vision: 1,
+            vision_mutation: 0,
+            max_vision: 10,
+            vision_cost: 1,
+            vision_shape: VisionShape::Line,
+            pheromone_layers: 1,
+            pheromone_emit: 100,
+            pheromone_evaporation: 5,
+            pheromone_diffusion: 10,
+            pheromone_mutation: 1,
+            share_ratio: 10,
+            share_kin_only: false,
+            kin_threshold: 2,
+            incubation: 0,
+            incubation_mutation: 0,
+            sleep_metabolism: 25,
+            sleep_defense: 50,
+            plant_regrowth: 1,
+            max_regrowth: 10,
+            terrains: [TerrainConfig {
+                max_food: 0,
+                spoil_threshold: 0,
+                spoil_rate: 0,
+            }; NUM_TERRAINS],
+            disease_initial: 0,
+            disease_transmission: 20,
+            disease_drain: 2,
+            disease_duration: 50,
+            immunity: 0,
+            immunity_mutation: 0,
+            immunity_cost: 0,
+            events: Vec::new(),
+            event_chances: [0.0; NUM_EVENT_KINDS],
+            event_radius: 50,
+            timeline: None,
+            temperature_pole: 20.0,
+            temperature_equator: 20.0,
+            temperature_noise: 0.0,
+            season_amplitude: 0.0,
+            season_length: 0,
+            thermal_optimum: 20.0,
+            thermal_mutation: 0.0,
+            thermal_cost: 0.0,
+            fibre_initial: 0,
+            fibre_regrowth: 0,
+            fibre_max: 100,
+            fibre_digestion: 0,
+            digestion_mutation: 0,
+            crowding_radius: 1,
+            crowding_threshold: 0,
+            crowding_cost: 0,
+            crowding_reproduction: 0,
+            max_population: 0,
+            world_generator: WorldGenerator::Flat,
+            world_seed: None,
+            generator_scale: 50,
+            generator_food: 200,
+            generator_regrowth: 0,
+            layout: None,
+            scenario: None,
+            spawn_strategy: SpawnStrategy::Uniform,
+            spawn_clusters: 10,
+            spawn_cluster_radius: 20,
+            genome_bank: None,
+        }
+    }
+}
+
+impl Config {
+    // Loads a config file made of "key = value" lines, '#' starts a comment
+    // Keys that are not present keep their default value
+    pub fn load(path: &str) -> Result<Config, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut config = Config::default();
+
+        for (num, line) in content.lines().enumerate() {
+            let line = parsing::strip_comment(line).trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut split = line.splitn(2, '=');
+            let key = split.next().unwrap().trim();
+            let value = match split.next() {
+                Some(v) => v.trim(),
+                None => return Err(format!("{}:{}: expected key = value", path, num + 1)),
+            };
+
+            config
+                .set(key, value)
+                .map_err(|e| format!("{}:{}: {}", path, num + 1, e))?;
+        }
+
+        Ok(config)
+    }
+
+    // Keys only read when the world and the initial creatures are created,
+    // changing them during a run would have no effect or break the world
+    pub fn is_startup_only(key: &str) -> bool {
+        const STARTUP_ONLY_KEYS: [&str; 21] = [
+            "width",
+            "height",
+            "num_creatures",
+            "pheromone_layers",
+            "timeline",
+            "temperature_pole",
+            "temperature_equator",
+            "temperature_noise",
+            "fibre_initial",
+            "disease_initial",
+            "world_generator",
+            "world_seed",
+            "generator_scale",
+            "generator_food",
+            "generator_regrowth",
+            "layout",
+            "scenario",
+            "spawn_strategy",
+            "spawn_clusters",
+            "spawn_cluster_radius",
+            "genome_bank",
+        ];
+        STARTUP_ONLY_KEYS.contains(&key)
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        fn parse<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+            value
+                .parse::<T>()
+                .map_err(|_| format!("invalid value '{}' for {}", value, key))
+        }
+
+        fn parse_percent(key: &str, value: &str) -> Result<u32, String> {
+            match parse(key, value)? {
+                v if v <= 100 => Ok(v),
+                _ => Err(format!("{} must be between 0 and 100", key)),
+            }
+        }
+
+        fn parse_positive(key: &str, value: &str) -> Result<f32, String> {
+            match parse(key, value)? {
+                v if v >= 0.0 => Ok(v),
+                _ => Err(format!("{} can't be negative", key)),
+            }
+        }
+
+        if let Some(name) = key.strip_prefix("cost.") {
+            return match Command::from_name(name) {
+                Some(command) => {
+                    self.command_costs[command as usize] = parse(key, value)?;
+                    Ok(())
+                }
+                None => Err(format!("unknown command '{}'", name)),
+            };
+        }
+
+        if let Some(name) = key.strip_prefix("event_chance.") {
+            let kind = match name {
+                "famine" => 0,
+                "meteor" => 1,
+                "boom" => 2,
+                "migration" => 3,
+                _ => return Err(format!("unknown event '{}'", name)),
+            };
+            self.event_chances[kind] = parse(key, value)?;
+            return Ok(());
+        }
+
+        let mut split = key.splitn(2, '.');
+        if let (Some(param), Some(name)) = (split.next(), split.next()) {
+            let terrain = match Terrain::from_name(name) {
+                Some(t) => &mut self.terrains[t as usize],
+                None => return Err(format!("unknown terrain '{}'", name)),
+            };
+            match param {
+                "max_food" => terrain.max_food = parse(key, value)?,
+                "spoil_threshold" => terrain.spoil_threshold = parse(key, value)?,
+                "spoil_rate" => terrain.spoil_rate = parse_percent(key, value)?,
+                _ => return Err(format!("unknown key '{}'", key)),
+            }
+            return Ok(());
+        }
+
+        match key {
+            "width" => self.width = parse(key, value)?,
+            "height" => self.height = parse(key, value)?,
+            "num_creatures" => self.num_creatures = parse(key, value)?,
+            "gene_cost" => self.gene_cost = parse(key, value)?,
+            "lifespan" => self.lifespan = parse(key, value)?,
+            "lifespan_mutation" => self.lifespan_mutation = parse(key, value)?,
+            "maturity_age" => self.maturity_age = parse(key, value)?,
+            "senescence_loss" => self.senescence_loss = parse(key, value)?,
+            "species_threshold" => self.species_threshold = parse(key, value)?,
+            "species_interval" => self.species_interval = parse(key, value)?,
+            "kill_carcass_ratio" => self.kill_carcass_ratio = parse_percent(key, value)?,
+            "carcass_decay" => self.carcass_decay = parse(key, value)?,
+            "strength" => self.strength = parse::<u32>(key, value)?.max(1),
+            "size" => self.size = parse::<u32>(key, value)?.max(1),
+            "armour" => self.armour = parse(key, value)?,
+            "combat_mutation" => self.combat_mutation = parse(key, value)?,
+            "combat_upkeep" => self.combat_upkeep = parse(key, value)?,
+            "action_model" => {
+                self.action_model = match value {
+                    "random" => ActionModel::Random,
+                    "directed" => ActionModel::Directed,
+                    _ => return Err(format!("invalid value '{}' for {}", value, key)),
+                }
+            }
+            "vision" => self.vision = parse::<u32>(key, value)?.max(1),
+            "vision_mutation" => self.vision_mutation = parse(key, value)?,
+            "max_vision" => self.max_vision = parse::<u32>(key, value)?.max(1),
+            "vision_cost" => self.vision_cost = parse(key, value)?,
+            "pheromone_layers" => self.pheromone_layers = parse::<usize>(key, value)?.max(1),
+            "pheromone_emit" => self.pheromone_emit = parse(key, value)?,
+            "pheromone_evaporation" => self.pheromone_evaporation = parse_percent(key, value)?,
+            "pheromone_diffusion" => self.pheromone_diffusion = parse_percent(key, value)?,
+            "pheromone_mutation" => self.pheromone_mutation = parse_percent(key, value)?,
+            "share_ratio" => self.share_ratio = parse_percent(key, value)?,
+            "share_kin_only" => self.share_kin_only = parse(key, value)?,
+            "kin_threshold" => self.kin_threshold = parse(key, value)?,
+            "incubation" => self.incubation = parse(key, value)?,
+            "incubation_mutation" => self.incubation_mutation = parse(key, value)?,
+            "sleep_metabolism" => self.sleep_metabolism = parse_percent(key, value)?,
+            "sleep_defense" => self.sleep_defense = parse_percent(key, value)?,
+            "plant_regrowth" => self.plant_regrowth = parse(key, value)?,
+            "max_regrowth" => self.max_regrowth = parse(key, value)?,
+            "disease_initial" => self.disease_initial = parse_percent(key, value)?,
+            "disease_transmission" => self.disease_transmission = parse_percent(key, value)?,
+            "disease_drain" => self.disease_drain = parse(key, value)?,
+            "disease_duration" => self.disease_duration = parse(key, value)?,
+            "immunity" => self.immunity = parse_percent(key, value)?,
+            "immunity_mutation" => self.immunity_mutation = parse(key, value)?,
+            "immunity_cost" => self.immunity_cost = parse(key, value)?,
+            "event" => {
+                let args: Vec<&str> = value.split_whitespace().collect();
+                if args.is_empty() {
+                    return Err(String::from("missing event round"));
+                }
+                let round = parse(key, args[0])?;
+                self.events.push((round, Event::parse(&args[1..])?));
+            }
+            "event_radius" => self.event_radius = parse(key, value)?,
+            "timeline" => self.timeline = Some(value.to_string()),
+            "temperature_pole" => self.temperature_pole = parse(key, value)?,
+            "temperature_equator" => self.temperature_equator = parse(key, value)?,
+            "temperature_noise" => self.temperature_noise = parse_positive(key, value)?,
+            "season_amplitude" => self.season_amplitude = parse(key, value)?,
+            "season_length" => self.season_length = parse(key, value)?,
+            "thermal_optimum" => self.thermal_optimum = parse(key, value)?,
+            "thermal_mutation" => self.thermal_mutation = parse_positive(key, value)?,
+            "thermal_cost" => self.thermal_cost = parse(key, value)?,
+            "fibre_initial" => self.fibre_initial = parse(key, value)?,
+            "fibre_regrowth" => self.fibre_regrowth = parse(key, value)?,
+            "fibre_max" => self.fibre_max = parse(key, value)?,
+            "fibre_digestion" => self.fibre_digestion = parse_percent(key, value)?,
+            "digestion_mutation" => self.digestion_mutation = parse(key, value)?,
+            "crowding_radius" => self.crowding_radius = parse(key, value)?,
+            "crowding_threshold" => self.crowding_threshold = parse(key, value)?,
+            "crowding_cost" => self.crowding_cost = parse(key, value)?,
+            "crowding_reproduction" => self.crowding_reproduction = parse(key, value)?,
+            "max_population" => self.max_population = parse(key, value)?,
+            "world_generator" => {
+                self.world_generator = match value {
+                    "flat" => WorldGenerator::Flat,
+                    "noise" => WorldGenerator::Noise,
+                    "islands" => WorldGenerator::Islands,
+                    "stripes" => WorldGenerator::Stripes,
+                    "oasis" => WorldGenerator::Oasis,
+                    _ => return Err(format!("invalid value '{}' for {}", value, key)),
+                }
+            }
+            "world_seed" => self.world_seed = Some(parse(key, value)?),
+            "generator_scale" => self.generator_scale = parse(key, value)?,
+            "generator_food" => self.generator_food = parse(key, value)?,
+            "generator_regrowth" => self.generator_regrowth = parse(key, value)?,
+            "layout" => self.layout = Some(value.to_string()),
+            "scenario" => self.scenario = Some(value.to_string()),
+            "spawn_strategy" => {
+                self.spawn_strategy = match value {
+                    "uniform" => SpawnStrategy::Uniform,
+                    "clustered" => SpawnStrategy::Clustered,
+                    "regions" => SpawnStrategy::Regions,
+                    "image" => SpawnStrategy::Image,
+                    _ => return Err(format!("invalid value '{}' for {}", value, key)),
+                }
+            }
+            "spawn_clusters" => self.spawn_clusters = parse(key, value)?,
+            "spawn_cluster_radius" => self.spawn_cluster_radius = parse(key, value)?,
+            "genome_bank" => self.genome_bank = Some(value.to_string()),
+            "vision_shape" => {
+                self.vision_shape = match value {
+                    "line" => VisionShape::Line,
+                    "quadrant" => VisionShape::Quadrant,
+                    _ => return Err(format!("invalid value '{}' for {}", value, key)),
+                }
+            }
+            _ => return Err(format!("unknown key '{}'", key)),
+        }
+
+        Ok(())
+    }
+
+    pub fn get_command_cost(&self, command: &Command) -> u32 {
+        self.command_costs[command.clone() as usize]
+    }
+}
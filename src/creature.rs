@@ -1,8 +1,11 @@
 pub mod command;
+pub mod traits;
 
+use crate::config::{ActionModel, Config};
 use crate::creaturemap::{CreatureId, CreatureMap};
 use crate::world::World;
 use command::Command;
+use traits::Traits;
 
 use rand::Rng;
 
@@ -29,18 +32,214 @@ impl fmt::Display for CreatureType {
 }
 
 
+#[derive(Clone, Copy)]
+pub enum Direction {
+    East,
+    South,
+    West,
+    North,
+}
+impl Direction {
+    pub fn random() -> Direction {
+        match rand::thread_rng().gen_range(0, 4) {
+            0 => Direction::East,
+            1 => Direction::South,
+            2 => Direction::West,
+            _ => Direction::North,
+        }
+    }
+
+    pub fn left(self) -> Direction {
+        match self {
+            Direction::East => Direction::North,
+            Direction::North => Direction::West,
+            Direction::West => Direction::South,
+            Direction::South => Direction::East,
+        }
+    }
+
+    pub fn right(self) -> Direction {
+        match self {
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+            Direction::North => Direction::East,
+        }
+    }
+
+    pub fn to_vec(self) -> (i8, i8) {
+        match self {
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+            Direction::North => (0, -1),
+        }
+    }
+}
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Direction::East => "E",
+                Direction::South => "S",
+                Direction::West => "W",
+                Direction::North => "N",
+            }
+        )
+    }
+}
+
+// A creature waiting to hatch, it holds the energy invested by its parent
+#[derive(Clone)]
+pub struct Egg {
+    pub uid: u64,
+    pub genes: Vec<Command>,
+    pub traits: Traits,
+    pub origin: Origin,
+    pub species: u32,
+    pub energy: u32,
+    pub hatch_round: u32,
+}
+impl fmt::Display for Egg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Egg c{}: Energy: {} Hatches: {} Genes: {}",
+            self.uid,
+            self.energy,
+            self.hatch_round,
+            self.genes.len()
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct Infection {
+    // Rounds left before recovering, unused when the disease lasts forever
+    pub remaining: u32,
+    // Number of creatures this infection was passed to
+    pub caused: u32,
+}
+
+// How a creature reacts when attacked until its next round
+#[derive(Clone)]
+pub enum Stance {
+    None,
+    Defend,
+    Flee,
+    // Lower metabolism but no more actions this round and weaker defense
+    Asleep,
+}
+
+#[derive(Clone)]
+pub struct Origin {
+    pub parent: Option<u64>,
+    pub birth_round: u32,
+    // Number of ancestors up to an initial creature
+    pub depth: u32,
+}
+impl Origin {
+    pub fn new(parent: Option<&CreatureStats>, round: u32) -> Origin {
+        match parent {
+            Some(p) => Origin {
+                parent: Some(p.id.get_uid()),
+                birth_round: round,
+                depth: p.origin.depth + 1,
+            },
+            None => Origin {
+                parent: None,
+                birth_round: round,
+                depth: 0,
+            },
+        }
+    }
+}
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.parent {
+            Some(p) => write!(f, "Parent: c{}", p)?,
+            None => write!(f, "Parent: None")?,
+        }
+        write!(f, " Born: {} Generation: {}", self.birth_round, self.depth)
+    }
+}
+
 #[derive(Clone)]
 pub struct CreatureStats {
     id: CreatureId,
     pos_x: u32,
     pos_y: u32,
     energy: u32,
+    age: u32,
+    traits: Traits,
+    origin: Origin,
+    species: u32,
+    // Cumulative energy gained from food and from other creatures
+    plant_energy: u64,
+    meat_energy: u64,
+    // Energy given to and received from other creatures with Share
+    donated: u64,
+    received: u64,
+    stance: Stance,
+    infection: Option<Infection>,
+    facing: Direction,
     e: u8,
     w: u8,
     n: u8,
     s: u8,
 }
 impl CreatureStats {
+    // Returns false if the creature was already infected or resisted
+    pub fn infect(&mut self, config: &Config) -> bool {
+        if self.infection.is_some() || rand::thread_rng().gen_range(0, 100) < self.traits.immunity {
+            return false;
+        }
+
+        self.infection = Some(Infection {
+            remaining: config.disease_duration,
+            caused: 0,
+        });
+        true
+    }
+
+    pub fn get_traits(&self) -> &Traits {
+        &self.traits
+    }
+
+    pub fn eat_plant(&mut self, energy: u32) {
+        self.energy += energy;
+        self.plant_energy += energy as u64;
+    }
+
+    pub fn eat_meat(&mut self, energy: u32) {
+        self.energy += energy;
+        self.meat_energy += energy as u64;
+    }
+
+    // 1.0 for a creature that only ate food, 2.0 for one that only fed on other creatures
+    // None until it gained energy either way
+    pub fn get_trophic_level(&self) -> Option<f32> {
+        let total = self.plant_energy + self.meat_energy;
+        if total == 0 {
+            return None;
+        }
+        Some(1.0 + (self.meat_energy as f32 / total as f32))
+    }
+
+    pub fn get_species(&self) -> u32 {
+        self.species
+    }
+
+    // Direction used by actions that target a neighbouring tile
+    pub fn get_action_dir(&self, config: &Config) -> (i8, i8) {
+        match config.action_model {
+            ActionModel::Random => self.get_proba_dir(),
+            ActionModel::Directed => self.facing.to_vec(),
+        }
+    }
+
     pub fn get_proba_dir(&self) -> (i8, i8) {
         let mut x_move: i8 = 0;
         let mut y_move: i8 = 0;
@@ -70,8 +269,27 @@ impl fmt::Display for CreatureStats {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Energy: {} E: {} W: {} N: {} S: {}",
-            self.energy, self.e, self.w, self.n, self.s
+            "Energy: {} Age: {} Species: {} Facing: {} E: {} W: {} N: {} S: {} Donated: {} Received: {}{}{}",
+            self.energy,
+            self.age,
+            self.species,
+            self.facing,
+            self.e,
+            self.w,
+            self.n,
+            self.s,
+            self.donated,
+            self.received,
+            if self.infection.is_some() {
+                " Infected"
+            } else {
+                ""
+            },
+            if let Stance::Asleep = self.stance {
+                " Asleep"
+            } else {
+                ""
+            }
         )
     }
 }
@@ -85,7 +303,14 @@ pub struct Creature {
     energy_loss: u32,
 }
 impl Creature {
-    pub fn new(id: CreatureId, x: u32, y: u32, genes: Vec<Command>) -> Creature {
+    pub fn new(
+        id: CreatureId,
+        x: u32,
+        y: u32,
+        genes: Vec<Command>,
+        traits: Traits,
+        origin: Origin,
+    ) -> Creature {
         let ctype = {
             let (mut eat_found, mut attack_found) = (false, false);
             for gene in &genes {
@@ -117,6 +342,17 @@ impl Creature {
                 pos_x: x,
                 pos_y: y,
                 energy: 100,
+                age: 0,
+                traits,
+                origin,
+                species: 0,
+                plant_energy: 0,
+                meat_energy: 0,
+                donated: 0,
+                received: 0,
+                stance: Stance::None,
+                infection: None,
+                facing: Direction::random(),
                 e: 128,
                 w: 128,
                 n: 128,
@@ -140,6 +376,55 @@ impl Creature {
         self.genes.len()
     }
 
+    pub fn get_genes(&self) -> &Vec<Command> {
+        &self.genes
+    }
+
+    pub fn get_species(&self) -> u32 {
+        self.stats.species
+    }
+
+    pub fn get_pos(&self) -> (u32, u32) {
+        (self.stats.pos_x, self.stats.pos_y)
+    }
+
+    pub fn set_pos(&mut self, x: u32, y: u32) {
+        self.stats.pos_x = x;
+        self.stats.pos_y = y;
+    }
+
+    pub fn get_energy(&self) -> u32 {
+        self.stats.energy
+    }
+
+    pub fn set_energy(&mut self, energy: u32) {
+        self.stats.energy = energy;
+    }
+
+    pub fn set_species(&mut self, species: u32) {
+        self.stats.species = species;
+    }
+
+    pub fn get_trophic_level(&self) -> Option<f32> {
+        self.stats.get_trophic_level()
+    }
+
+    pub fn get_infection(&self) -> Option<&Infection> {
+        self.stats.infection.as_ref()
+    }
+
+    pub fn infect(&mut self, config: &Config) -> bool {
+        self.stats.infect(config)
+    }
+
+    pub fn get_age(&self) -> u32 {
+        self.stats.age
+    }
+
+    pub fn get_traits(&self) -> &Traits {
+        &self.stats.traits
+    }
+
     pub fn adjust_pos(
         world: &World,
         (pos_x, pos_y): (u32, u32),
@@ -163,37 +448,123 @@ impl Creature {
         (ret_v.0 as u32, ret_v.1 as u32)
     }
 
-    pub fn simulate(&mut self, world: &mut World, cmap: &mut CreatureMap) -> bool {
+    // A rested creature slept for the whole round
+    pub fn get_energy_loss(&self, world: &World, config: &Config, rested: bool) -> u32 {
+        let mut loss = self.energy_loss + (self.genes.len() as u32 * config.gene_cost);
+
+        let traits = &self.stats.traits;
+        loss += ((traits.strength + traits.size + traits.armour) * config.combat_upkeep) / 100;
+        loss += (traits.vision - 1) * config.vision_cost;
+        loss += (traits.immunity * config.immunity_cost) / 100;
+        if self.stats.infection.is_some() {
+            loss += config.disease_drain;
+        }
+
+        if config.crowding_cost != 0 {
+            let (x, y) = (self.stats.pos_x, self.stats.pos_y);
+            let neighbours = world.count_neighbours(x, y, config.crowding_radius);
+            loss += neighbours.saturating_sub(config.crowding_threshold) * config.crowding_cost;
+        }
+
+        let temperature = world.get_temperature(self.stats.pos_x, self.stats.pos_y);
+        loss += ((temperature - traits.thermal_optimum).abs() * config.thermal_cost) as u32;
+
+        let lifespan = self.stats.traits.lifespan;
+        if lifespan != 0 {
+            let age = std::cmp::min(self.stats.age, lifespan);
+            loss += ((config.senescence_loss as u64 * age as u64) / lifespan as u64) as u32;
+        }
+
+        if rested {
+            loss = (loss * config.sleep_metabolism) / 100;
+        }
+
+        loss
+    }
+
+    fn update_infection(&mut self, world: &World, cmap: &mut CreatureMap, config: &Config) {
+        let mut caused = 0;
+        for dir in &[(1, 0), (-1, 0), (0, -1), (0, 1)] {
+            let (x, y) = Creature::adjust_pos(world, (self.stats.pos_x, self.stats.pos_y), *dir);
+            if let Some(id) = world.get_other_creature(x, y, &self.stats.id) {
+                if rand::thread_rng().gen_range(0, 100) < config.disease_transmission
+                    && cmap.get_creature_mut(id).unwrap().infect(config)
+                {
+                    caused += 1;
+                }
+            }
+        }
+
+        let infection = self.stats.infection.as_mut().unwrap();
+        infection.caused += caused;
+
+        if config.disease_duration != 0 {
+            infection.remaining = infection.remaining.saturating_sub(1);
+            if infection.remaining == 0 {
+                cmap.end_infection(infection.caused);
+                self.stats.infection = None;
+            }
+        }
+    }
+
+    pub fn simulate(&mut self, world: &mut World, cmap: &mut CreatureMap, config: &Config) -> bool {
         if self.stats.energy == 0 {
+            // Killed earlier in the round or never given energy, the tile must not keep a dead id
+            let tile = world.get_tile_mut(self.stats.pos_x, self.stats.pos_y);
+            if tile.creature.as_ref() == Some(&self.stats.id) {
+                tile.creature = None;
+            }
             return false;
         }
 
-        for g in &self.genes {
-            g.execute(world, &mut self.stats, &self.genes, cmap);
+        self.stats.age += 1;
+        self.stats.stance = Stance::None;
+
+        // Falling asleep after acting still protects the creature but doesn't save energy
+        let mut rested = false;
+        for (i, g) in self.genes.iter().enumerate() {
+            g.execute(world, &mut self.stats, &self.genes, cmap, config);
+            if let Stance::Asleep = self.stats.stance {
+                rested = i == 0;
+                break;
+            }
+        }
+
+        // The drain of a disease applies to the round the creature recovers
+        let energy_loss = self.get_energy_loss(world, config, rested);
+
+        if self.stats.infection.is_some() {
+            self.update_infection(world, cmap, config);
         }
 
         let cur_tile = world.get_tile_mut(self.stats.pos_x, self.stats.pos_y);
 
-        if self.stats.energy <= self.energy_loss {
-            cur_tile.food += self.stats.energy;
+        let lifespan = self.stats.traits.lifespan;
+
+        if self.stats.energy <= energy_loss || (lifespan != 0 && self.stats.age >= lifespan) {
+            cur_tile.carcass += self.stats.energy;
             self.stats.energy = 0;
-            // Death by starvation
+            // Death by starvation or old age, the caller deallocates it
             cur_tile.creature = None;
-            cmap.deallocate(self.stats.id.clone());
             false
         } else {
-            cur_tile.food += self.energy_loss;
-            self.stats.energy -= self.energy_loss;
+            cur_tile.food += energy_loss;
+            self.stats.energy -= energy_loss;
             true
         }
     }
 }
 impl fmt::Display for Creature {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "==Creature==\n")?;
+        write!(f, "==Creature c{}==\n", self.stats.id.get_uid())?;
 
-        write!(f, "Type: {}\n", self.ctype)?;
+        match self.get_trophic_level() {
+            Some(t) => write!(f, "Type: {}(trophic level: {:.2})\n", self.ctype, t)?,
+            None => write!(f, "Type: {}(trophic level: unknown)\n", self.ctype)?,
+        }
         write!(f, "Stats: {}\n", self.stats)?;
+        write!(f, "Traits: {}\n", self.stats.traits)?;
+        write!(f, "Origin: {}\n", self.stats.origin)?;
         write!(f, "Genes({}):\n", self.genes.len())?;
 
         for gene in &self.genes {
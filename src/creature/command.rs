@@ -1,7 +1,9 @@
 
-use crate::creature::{Creature, CreatureStats};
+use crate::config::{ActionModel, Config, VisionShape};
+use crate::creature::{Creature, CreatureStats, Direction, Stance};
 use crate::creaturemap::CreatureMap;
-use crate::world::World;
+use crate::species::genome_distance;
+use crate::world::{Tile, World};
 
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
@@ -9,10 +11,10 @@ use rand::Rng;
 
 use std::fmt;
 
-const NUM_COMMANDS: u32 = 8;
+pub const NUM_COMMANDS: u32 = 22;
 pub const NUM_MAX_GENES: usize = 16;
 
-#[derive(Clone, FromPrimitive)]
+#[derive(Clone, PartialEq, Eq, Hash, FromPrimitive)]
 pub enum Command {
     Nop = 0,
     LookForFood,
@@ -22,6 +24,20 @@ pub enum Command {
     Attack,
     Reproduce,
     Invert,
+    Scavenge,
+    Defend,
+    Flee,
+    TurnLeft,
+    TurnRight,
+    MoveForward,
+    EatAhead,
+    Emit,
+    Smell,
+    Share,
+    Sleep,
+    Plant,
+    Build,
+    Dig,
 }
 impl fmt::Display for Command {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -37,6 +53,20 @@ impl fmt::Display for Command {
                 Command::Attack => "Attack",
                 Command::Reproduce => "Reproduce",
                 Command::Invert => "Invert",
+                Command::Scavenge => "Scavenge",
+                Command::Defend => "Defend",
+                Command::Flee => "Flee",
+                Command::TurnLeft => "TurnLeft",
+                Command::TurnRight => "TurnRight",
+                Command::MoveForward => "MoveForward",
+                Command::EatAhead => "EatAhead",
+                Command::Emit => "Emit",
+                Command::Smell => "Smell",
+                Command::Share => "Share",
+                Command::Sleep => "Sleep",
+                Command::Plant => "Plant",
+                Command::Build => "Build",
+                Command::Dig => "Dig",
             }
         )
     }
@@ -44,6 +74,37 @@ impl fmt::Display for Command {
 
 
 impl Command {
+    // Names are the ones displayed, case is ignored
+    pub fn from_name(name: &str) -> Option<Command> {
+        (0..NUM_COMMANDS)
+            .map(|i| FromPrimitive::from_u32(i).unwrap())
+            .find(|c: &Command| c.to_string().eq_ignore_ascii_case(name))
+    }
+
+    // Parses a comma separated list of names into a genome of 1 to NUM_MAX_GENES genes
+    pub fn parse_genes(list: &str) -> Result<Vec<Command>, String> {
+        let genes = list
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                Command::from_name(name).ok_or_else(|| format!("unknown command '{}'", name))
+            })
+            .collect::<Result<Vec<Command>, String>>()?;
+
+        if genes.is_empty() {
+            return Err(String::from("empty genome"));
+        }
+        if genes.len() > NUM_MAX_GENES {
+            return Err(format!(
+                "a genome has at most {} genes, found {}",
+                NUM_MAX_GENES,
+                genes.len()
+            ));
+        }
+        Ok(genes)
+    }
+
     #[inline(always)]
     pub fn execute(
         &self,
@@ -51,66 +112,182 @@ impl Command {
         stats: &mut CreatureStats,
         gene: &Vec<Command>,
         creatures: &mut CreatureMap,
+        config: &Config,
     ) {
+        let cost = config.get_command_cost(self);
+        if cost != 0 {
+            // Spent energy goes back to the environment like metabolic loss
+            let cost = std::cmp::min(cost, stats.energy);
+            stats.energy -= cost;
+            world.get_tile_mut(stats.pos_x, stats.pos_y).food += cost;
+        }
+
         match self {
             Command::Nop => {}
-            Command::LookForFood => Command::c_lookfood(world, stats),
-            Command::LookForCreature => Command::c_lookcreatures(world, stats),
-            Command::Move => Command::c_move(world, stats),
-            Command::Eat => Command::c_eat(world, stats),
-            Command::Attack => Command::c_attack(world, stats, creatures),
-            Command::Reproduce => Command::c_reproduce(world, stats, gene, creatures),
-            Command::Invert => Command::c_invert(stats),
+            Command::LookForFood => match config.action_model {
+                ActionModel::Random => Command::c_lookfood(world, stats, config),
+                ActionModel::Directed => {
+                    Command::c_lookahead(world, stats, |t| t.food != 0 || t.fibre != 0)
+                }
+            },
+            Command::LookForCreature => match config.action_model {
+                ActionModel::Random => Command::c_lookcreatures(world, stats, config),
+                ActionModel::Directed => {
+                    Command::c_lookahead(world, stats, |t| t.creature.is_some())
+                }
+            },
+            Command::Move => {
+                let dir = stats.get_action_dir(config);
+                Command::c_move(world, stats, dir)
+            }
+            Command::Eat => {
+                let dir = stats.get_action_dir(config);
+                Command::c_eat(world, stats, dir)
+            }
+            Command::Attack => Command::c_attack(world, stats, creatures, config),
+            Command::Reproduce => Command::c_reproduce(world, stats, gene, creatures, config),
+            Command::Invert => match config.action_model {
+                ActionModel::Random => Command::c_invert(stats),
+                ActionModel::Directed => stats.facing = stats.facing.left().left(),
+            },
+            Command::Scavenge => Command::c_scavenge(world, stats, config),
+            Command::Defend => Command::c_stance(stats, Stance::Defend),
+            Command::Flee => Command::c_stance(stats, Stance::Flee),
+            Command::TurnLeft => stats.facing = stats.facing.left(),
+            Command::TurnRight => stats.facing = stats.facing.right(),
+            Command::MoveForward => {
+                let dir = stats.facing.to_vec();
+                Command::c_move(world, stats, dir)
+            }
+            Command::EatAhead => {
+                let dir = stats.facing.to_vec();
+                Command::c_eat(world, stats, dir)
+            }
+            Command::Emit => world.add_pheromone(
+                stats.traits.pheromone,
+                stats.pos_x,
+                stats.pos_y,
+                config.pheromone_emit as f32,
+            ),
+            Command::Smell => Command::c_smell(world, stats, config),
+            Command::Share => Command::c_share(world, stats, gene, creatures, config),
+            Command::Sleep => Command::c_stance(stats, Stance::Asleep),
+            Command::Plant => Command::c_plant(world, stats, config),
+            Command::Build => Command::c_build(world, stats, config),
+            Command::Dig => Command::c_dig(world, stats, config),
         }
     }
 
+    // Distance weighted average of value over the tiles visible in a direction
     #[inline(always)]
-    fn c_lookfood(world: &World, stats: &mut CreatureStats) {
-        let update_for_dir = |pos_x: u32, pos_y: u32, dir_x: i8, dir_y: i8| -> u8 {
-            let (want_x, want_y) = Creature::adjust_pos(world, (pos_x, pos_y), (dir_x, dir_y));
-            let tile = world.get_tile(want_x, want_y);
-            if tile.food >= 255 {
-                255
-            } else {
-                tile.food as u8
+    fn sense_dir(
+        world: &World,
+        stats: &CreatureStats,
+        config: &Config,
+        (dir_x, dir_y): (i8, i8),
+        value: impl Fn(&Tile) -> u32,
+    ) -> u8 {
+        let (dir_x, dir_y) = (dir_x as i64, dir_y as i64);
+        let (mut sum, mut weights) = (0.0, 0.0);
+
+        for dist in 1..=(stats.traits.vision as i64) {
+            let spread = match config.vision_shape {
+                VisionShape::Line => 0,
+                VisionShape::Quadrant => dist,
+            };
+            let weight = 1.0 / dist as f32;
+
+            for side in -spread..=spread {
+                let x = stats.pos_x as i64 + (dir_x * dist) - (dir_y * side);
+                let y = stats.pos_y as i64 + (dir_y * dist) + (dir_x * side);
+                sum += value(world.get_tile_wrapped(x, y)) as f32 * weight;
+                weights += weight;
             }
+        }
+
+        (sum / weights).min(255.0) as u8
+    }
+
+    #[inline(always)]
+    fn c_lookfood(world: &World, stats: &mut CreatureStats, config: &Config) {
+        // Nutrients are weighted by how well the creature digests them
+        let fibre_digestion = stats.traits.fibre_digestion as u64;
+        let food = move |t: &Tile| {
+            ((t.food as u64 * (100 - fibre_digestion) + t.fibre as u64 * fibre_digestion) / 100)
+                as u32
         };
 
-        let (x, y) = (stats.pos_x, stats.pos_y);
+        stats.e = Command::sense_dir(world, stats, config, (1, 0), food);
+        stats.w = Command::sense_dir(world, stats, config, (-1, 0), food);
+        stats.n = Command::sense_dir(world, stats, config, (0, -1), food);
+        stats.s = Command::sense_dir(world, stats, config, (0, 1), food);
+    }
+
+    #[inline(always)]
+    fn c_lookcreatures(world: &World, stats: &mut CreatureStats, config: &Config) {
+        let creature = |t: &Tile| if t.creature.is_some() { 255 } else { 0 };
 
-        stats.e = update_for_dir(x, y, 1, 0);
-        stats.w = update_for_dir(x, y, -1, 0);
-        stats.n = update_for_dir(x, y, 0, -1);
-        stats.s = update_for_dir(x, y, 0, 1);
+        stats.e = Command::sense_dir(world, stats, config, (1, 0), creature);
+        stats.w = Command::sense_dir(world, stats, config, (-1, 0), creature);
+        stats.n = Command::sense_dir(world, stats, config, (0, -1), creature);
+        stats.s = Command::sense_dir(world, stats, config, (0, 1), creature);
     }
 
+    // Only sees in front, turns away if there is nothing of interest in its vision range
     #[inline(always)]
-    fn c_lookcreatures(world: &World, stats: &mut CreatureStats) {
-        let update_for_dir = |pos_x: u32, pos_y: u32, dir_x: i8, dir_y: i8| -> u8 {
-            let (want_x, want_y) = Creature::adjust_pos(world, (pos_x, pos_y), (dir_x, dir_y));
-            let tile = world.get_tile(want_x, want_y);
-            if tile.creature == None {
-                0
+    fn c_lookahead(world: &World, stats: &mut CreatureStats, interesting: fn(&Tile) -> bool) {
+        let (dir_x, dir_y) = stats.facing.to_vec();
+
+        let seen = (1..=(stats.traits.vision as i64)).any(|dist| {
+            let x = stats.pos_x as i64 + (dir_x as i64 * dist);
+            let y = stats.pos_y as i64 + (dir_y as i64 * dist);
+            interesting(world.get_tile_wrapped(x, y))
+        });
+
+        if !seen {
+            stats.facing = if rand::thread_rng().gen() {
+                stats.facing.left()
             } else {
-                255
-            }
-        };
+                stats.facing.right()
+            };
+        }
+    }
 
-        let (x, y) = (stats.pos_x, stats.pos_y);
+    // Sets the direction weights from the pheromone of the neighbouring tiles
+    // or faces the strongest scent with the directed action model
+    #[inline(always)]
+    fn c_smell(world: &World, stats: &mut CreatureStats, config: &Config) {
+        let (pos, layer) = ((stats.pos_x, stats.pos_y), stats.traits.pheromone);
+        let smell_dir = |dir: Direction| -> f32 {
+            let (want_x, want_y) = Creature::adjust_pos(world, pos, dir.to_vec());
+            world.get_pheromone(layer, want_x, want_y)
+        };
 
-        stats.e = update_for_dir(x, y, 1, 0);
-        stats.w = update_for_dir(x, y, -1, 0);
-        stats.n = update_for_dir(x, y, 0, -1);
-        stats.s = update_for_dir(x, y, 0, 1);
+        match config.action_model {
+            ActionModel::Random => {
+                let weight = |dir: Direction| -> u8 { smell_dir(dir).min(255.0) as u8 };
+                stats.e = weight(Direction::East);
+                stats.w = weight(Direction::West);
+                stats.n = weight(Direction::North);
+                stats.s = weight(Direction::South);
+            }
+            ActionModel::Directed => {
+                let mut best = stats.facing;
+                for dir in &[stats.facing.left(), stats.facing.right()] {
+                    if smell_dir(*dir) > smell_dir(best) {
+                        best = *dir;
+                    }
+                }
+                stats.facing = best;
+            }
+        }
     }
 
     #[inline(always)]
-    fn c_move(world: &mut World, stats: &mut CreatureStats) {
-        let dir = stats.get_proba_dir();
-
+    fn c_move(world: &mut World, stats: &mut CreatureStats, dir: (i8, i8)) {
         let (want_x, want_y) = Creature::adjust_pos(world, (stats.pos_x, stats.pos_y), dir);
 
-        if let None = world.get_tile(want_x, want_y).creature {
+        if !world.get_tile(want_x, want_y).is_blocked() {
             world.get_tile_mut(stats.pos_x, stats.pos_y).creature = None;
             world.get_tile_mut(want_x, want_y).creature = Some(stats.id.clone());
             stats.pos_x = want_x;
@@ -119,70 +296,194 @@ impl Command {
     }
 
     #[inline(always)]
-    fn c_eat(world: &mut World, stats: &mut CreatureStats) {
-        let dir = stats.get_proba_dir();
-
+    fn c_eat(world: &mut World, stats: &mut CreatureStats, dir: (i8, i8)) {
         let (want_x, want_y) = Creature::adjust_pos(world, (stats.pos_x, stats.pos_y), dir);
         let mut tile = world.get_tile_mut(want_x, want_y);
 
-        if let Some(_) = tile.creature {
+        if tile.is_blocked() {
             return;
         }
 
         const FOOD_TAKEN: u32 = 10;
 
-        if tile.food >= FOOD_TAKEN {
-            stats.energy += FOOD_TAKEN;
-            tile.food -= FOOD_TAKEN;
-        } else {
-            stats.energy += tile.food;
-            tile.food = 0;
+        // Each nutrient is only eaten if it can be digested at all, the undigested part is lost
+        let fibre_digestion = stats.traits.fibre_digestion;
+        let mut energy = 0;
+
+        if fibre_digestion != 100 {
+            let taken = std::cmp::min(tile.food, FOOD_TAKEN);
+            tile.food -= taken;
+            energy += (taken * (100 - fibre_digestion)) / 100;
         }
+        if fibre_digestion != 0 {
+            let taken = std::cmp::min(tile.fibre, FOOD_TAKEN);
+            tile.fibre -= taken;
+            energy += (taken * fibre_digestion) / 100;
+        }
+
+        stats.eat_plant(energy);
     }
 
     #[inline(always)]
-    fn c_attack(world: &mut World, stats: &mut CreatureStats, creatures: &mut CreatureMap) {
-        let dir = stats.get_proba_dir();
+    fn c_scavenge(world: &mut World, stats: &mut CreatureStats, config: &Config) {
+        let dir = stats.get_action_dir(config);
 
         let (want_x, want_y) = Creature::adjust_pos(world, (stats.pos_x, stats.pos_y), dir);
-        let mut tile = world.get_tile_mut(want_x, want_y);
+        let tile = world.get_tile_mut(want_x, want_y);
 
-        if let None = tile.creature {
+        if tile.is_blocked() {
             return;
         }
 
-        const ENERGY_TAKEN: u32 = 20;
+        const MEAT_TAKEN: u32 = 20;
 
-        let id_victim = tile.creature.clone().unwrap();
+        let taken = std::cmp::min(tile.carcass, MEAT_TAKEN);
+        stats.eat_meat(taken);
+        tile.carcass -= taken;
+    }
+
+    #[inline(always)]
+    fn c_attack(
+        world: &mut World,
+        stats: &mut CreatureStats,
+        creatures: &mut CreatureMap,
+        config: &Config,
+    ) {
+        let dir = stats.get_action_dir(config);
+
+        let (want_x, want_y) = Creature::adjust_pos(world, (stats.pos_x, stats.pos_y), dir);
+
+        let id_victim = match world.get_other_creature(want_x, want_y, &stats.id) {
+            Some(id) => id,
+            None => {
+                // Eggs can't defend themselves
+                if let Some(egg) = world.get_tile_mut(want_x, want_y).egg.take() {
+                    stats.eat_meat(egg.energy);
+                    creatures.destroy_egg(&egg);
+                }
+                return;
+            }
+        };
         let victim = creatures.get_creature_mut(id_victim.clone()).unwrap();
-        if victim.stats.energy <= ENERGY_TAKEN {
-            // Kills it
-            stats.energy += victim.stats.energy;
+
+        if let Stance::Flee = victim.stats.stance {
+            let dir = victim.stats.get_action_dir(config);
+            Command::c_move(world, &mut victim.stats, dir);
+            if (victim.stats.pos_x, victim.stats.pos_y) != (want_x, want_y) {
+                return;
+            }
+        }
+
+        const BASE_DAMAGE: u32 = 20;
+
+        let defending = matches!(victim.stats.stance, Stance::Defend);
+        let (att, def) = (&stats.traits, &victim.stats.traits);
+
+        let attack = att.strength + (stats.energy / 20);
+        let mut defense = def.size + def.armour + (victim.stats.energy / 20);
+        if defending {
+            defense *= 2;
+        } else if let Stance::Asleep = victim.stats.stance {
+            defense = (defense * config.sleep_defense) / 100;
+        }
+
+        if rand::thread_rng().gen_range(0, attack + defense) >= attack {
+            if defending {
+                // The victim fights back
+                let damage = (BASE_DAMAGE * def.strength) / ((att.size + att.armour) * 2);
+                let damage = std::cmp::min(damage, stats.energy);
+                stats.energy -= damage;
+                victim.stats.eat_meat(damage);
+            }
+            return;
+        }
+
+        let mut damage = (BASE_DAMAGE * att.strength) / (def.size + def.armour);
+        if defending {
+            damage /= 2;
+        }
+        let damage = std::cmp::max(damage, 1);
+
+        if victim.stats.energy <= damage {
+            // Kills it, what isn't eaten is left as a carcass
+            let carcass = (victim.stats.energy * config.kill_carcass_ratio) / 100;
+            stats.eat_meat(victim.stats.energy - carcass);
+            let tile = world.get_tile_mut(want_x, want_y);
+            tile.carcass += carcass;
             tile.creature = None;
             creatures.deallocate(id_victim);
         } else {
-            victim.stats.energy -= ENERGY_TAKEN;
-            stats.energy += ENERGY_TAKEN;
+            victim.stats.energy -= damage;
+            stats.eat_meat(damage);
         }
     }
 
+    #[inline(always)]
+    fn c_share(
+        world: &World,
+        stats: &mut CreatureStats,
+        gene: &[Command],
+        creatures: &mut CreatureMap,
+        config: &Config,
+    ) {
+        let dir = stats.get_action_dir(config);
+
+        let (want_x, want_y) = Creature::adjust_pos(world, (stats.pos_x, stats.pos_y), dir);
+
+        let id_other = match world.get_other_creature(want_x, want_y, &stats.id) {
+            Some(id) => id,
+            None => return,
+        };
+        let other = creatures.get_creature_mut(id_other).unwrap();
+
+        if config.share_kin_only && genome_distance(gene, other.get_genes()) > config.kin_threshold
+        {
+            return;
+        }
+
+        let amount = (stats.energy * config.share_ratio) / 100;
+        stats.energy -= amount;
+        stats.donated += amount as u64;
+        other.stats.energy += amount;
+        other.stats.received += amount as u64;
+    }
+
+    #[inline(always)]
+    fn c_stance(stats: &mut CreatureStats, stance: Stance) {
+        stats.stance = stance;
+    }
+
     #[inline(always)]
     fn c_reproduce(
         world: &mut World,
         stats: &mut CreatureStats,
         gene: &Vec<Command>,
         creatures: &mut CreatureMap,
+        config: &Config,
     ) {
-        if stats.energy < 200 {
+        if stats.energy < 200 || stats.age < config.maturity_age {
+            return;
+        }
+
+        if config.max_population != 0
+            && creatures.get_population() >= config.max_population as usize
+        {
+            return;
+        }
+
+        if config.crowding_reproduction != 0
+            && world.count_neighbours(stats.pos_x, stats.pos_y, config.crowding_radius)
+                >= config.crowding_reproduction
+        {
             return;
         }
 
-        let dir = stats.get_proba_dir();
+        let dir = stats.get_action_dir(config);
 
         let (want_x, want_y) = Creature::adjust_pos(world, (stats.pos_x, stats.pos_y), dir);
         let mut tile = world.get_tile_mut(want_x, want_y);
 
-        if let Some(_) = tile.creature {
+        if tile.is_blocked() {
             return;
         }
 
@@ -203,10 +504,54 @@ impl Command {
             }
         }
 
-        let id = creatures.add_creature(want_x, want_y, new_genes);
-        tile.creature = Some(id);
+        const ENERGY_GIVEN: u32 = 100;
+
+        let traits = stats.traits.inherit(config);
+        if stats.traits.incubation != 0 {
+            if tile.egg.is_some() {
+                return;
+            }
+            let egg = creatures.lay_egg(new_genes, traits, stats, ENERGY_GIVEN);
+            tile.egg = Some(Box::new(egg));
+        } else {
+            let id = creatures.add_creature(want_x, want_y, new_genes, traits, Some(stats));
+            tile.creature = Some(id);
+        }
+
+        stats.energy -= ENERGY_GIVEN;
+    }
+
+    #[inline(always)]
+    fn c_plant(world: &mut World, stats: &mut CreatureStats, config: &Config) {
+        let dir = stats.get_action_dir(config);
+
+        let (want_x, want_y) = Creature::adjust_pos(world, (stats.pos_x, stats.pos_y), dir);
+        let tile = world.get_tile_mut(want_x, want_y);
 
-        stats.energy -= 100;
+        if !tile.wall {
+            tile.regrowth =
+                std::cmp::min(tile.regrowth + config.plant_regrowth, config.max_regrowth);
+        }
+    }
+
+    #[inline(always)]
+    fn c_build(world: &mut World, stats: &mut CreatureStats, config: &Config) {
+        let dir = stats.get_action_dir(config);
+
+        let (want_x, want_y) = Creature::adjust_pos(world, (stats.pos_x, stats.pos_y), dir);
+        let tile = world.get_tile_mut(want_x, want_y);
+
+        if !tile.is_blocked() && tile.egg.is_none() {
+            tile.wall = true;
+        }
+    }
+
+    #[inline(always)]
+    fn c_dig(world: &mut World, stats: &mut CreatureStats, config: &Config) {
+        let dir = stats.get_action_dir(config);
+
+        let (want_x, want_y) = Creature::adjust_pos(world, (stats.pos_x, stats.pos_y), dir);
+        world.get_tile_mut(want_x, want_y).wall = false;
     }
 
     #[inline(always)]
@@ -0,0 +1,111 @@
+use crate::config::Config;
+
+use rand::Rng;
+
+use std::fmt;
+
+// Heritable values that are not encoded in the genes
+#[derive(Clone)]
+pub struct Traits {
+    // 0 means the creature never dies of old age
+    pub lifespan: u32,
+    // Used to attack and to fight back
+    pub strength: u32,
+    // Both make the creature harder to hurt
+    pub size: u32,
+    pub armour: u32,
+    // Number of tiles seen by sensing commands in each direction
+    pub vision: u32,
+    // Pheromone layer used by Emit and Smell
+    pub pheromone: usize,
+    // Rounds an egg takes to hatch, 0 means children are born directly
+    pub incubation: u32,
+    // Chance in percent to resist an infection
+    pub immunity: u32,
+    // Temperature at which the creature doesn't lose extra energy
+    pub thermal_optimum: f32,
+    // Percentage of the energy extracted from fibre, the rest is extracted from food
+    pub fibre_digestion: u32,
+}
+impl Traits {
+    pub fn new(config: &Config) -> Traits {
+        Traits {
+            lifespan: config.lifespan,
+            strength: config.strength,
+            size: config.size,
+            armour: config.armour,
+            vision: config.vision,
+            pheromone: 0,
+            incubation: config.incubation,
+            immunity: config.immunity,
+            thermal_optimum: config.thermal_optimum,
+            fibre_digestion: config.fibre_digestion,
+        }
+    }
+
+    pub fn inherit(&self, config: &Config) -> Traits {
+        let mut traits = self.clone();
+
+        if traits.lifespan != 0 {
+            traits.lifespan = mutate_value(traits.lifespan, config.lifespan_mutation, 1);
+        }
+
+        traits.strength = mutate_value(traits.strength, config.combat_mutation, 1);
+        traits.size = mutate_value(traits.size, config.combat_mutation, 1);
+        traits.armour = mutate_value(traits.armour, config.combat_mutation, 0);
+        traits.vision = std::cmp::min(
+            mutate_value(traits.vision, config.vision_mutation, 1),
+            config.max_vision,
+        );
+
+        traits.incubation = mutate_value(traits.incubation, config.incubation_mutation, 0);
+        traits.immunity = std::cmp::min(
+            mutate_value(traits.immunity, config.immunity_mutation, 0),
+            100,
+        );
+
+        traits.fibre_digestion = std::cmp::min(
+            mutate_value(traits.fibre_digestion, config.digestion_mutation, 0),
+            100,
+        );
+
+        if config.thermal_mutation != 0.0 {
+            traits.thermal_optimum +=
+                rand::thread_rng().gen_range(-config.thermal_mutation, config.thermal_mutation);
+        }
+
+        if rand::thread_rng().gen_range(0, 100) < config.pheromone_mutation {
+            traits.pheromone = rand::thread_rng().gen_range(0, config.pheromone_layers);
+        }
+
+        traits
+    }
+}
+impl fmt::Display for Traits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Lifespan: {} Strength: {} Size: {} Armour: {} Vision: {} Pheromone: {} Incubation: {} Immunity: {} Thermal optimum: {:.1} Fibre digestion: {}",
+            self.lifespan,
+            self.strength,
+            self.size,
+            self.armour,
+            self.vision,
+            self.pheromone,
+            self.incubation,
+            self.immunity,
+            self.thermal_optimum,
+            self.fibre_digestion
+        )
+    }
+}
+
+// Randomly moves value by up to max_delta in either direction without going under min
+pub fn mutate_value(value: u32, max_delta: u32, min: u32) -> u32 {
+    if max_delta == 0 {
+        return value;
+    }
+
+    let delta = rand::thread_rng().gen_range(0, (max_delta * 2) + 1) as i64 - max_delta as i64;
+    std::cmp::max(value as i64 + delta, min as i64) as u32
+}
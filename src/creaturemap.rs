@@ -1,5 +1,7 @@
 use crate::creature::command::Command;
-use crate::creature::Creature;
+use crate::creature::traits::Traits;
+use crate::creature::{Creature, CreatureStats, Egg, Origin};
+use crate::lineage::Ancestry;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 static GENCOUNTER: AtomicU64 = AtomicU64::new(0);
@@ -16,24 +18,67 @@ impl CreatureId {
     pub fn get_index(&self) -> usize {
         self.index
     }
+
+    // Unique for the whole run, unlike the index which is reused
+    pub fn get_uid(&self) -> u64 {
+        self.generation
+    }
 }
 
 pub struct CreatureMap {
     map: Vec<Option<Creature>>,
     free: Vec<usize>,
+    ancestry: Ancestry,
+    round: u32,
+    // Used to estimate the basic reproduction number of the disease
+    ended_infections: u32,
+    secondary_infections: u64,
+    // Eggs laid that haven't hatched or been destroyed yet
+    num_eggs: usize,
 }
 impl CreatureMap {
     pub fn new() -> CreatureMap {
         CreatureMap {
             map: Vec::with_capacity(250000),
             free: Vec::with_capacity(250000),
+            ancestry: Ancestry::new(),
+            round: 0,
+            ended_infections: 0,
+            secondary_infections: 0,
+            num_eggs: 0,
+        }
+    }
+
+    pub fn set_round(&mut self, round: u32) {
+        self.round = round;
+    }
+
+    pub fn get_ancestry(&self) -> &Ancestry {
+        &self.ancestry
+    }
+
+    pub fn end_infection(&mut self, caused: u32) {
+        self.ended_infections += 1;
+        self.secondary_infections += caused as u64;
+    }
+
+    // Mean number of creatures infected by each infection that ended
+    pub fn get_r0(&self) -> Option<f32> {
+        if self.ended_infections == 0 {
+            return None;
         }
+        Some(self.secondary_infections as f32 / self.ended_infections as f32)
     }
 
     pub fn get_num(&self) -> usize {
         self.map.len()
     }
 
+    // Living creatures and eggs that are yet to hatch
+    pub fn get_population(&self) -> usize {
+        self.map.len() - self.free.len() + self.num_eggs
+    }
+
     pub fn get_creatureid_by_index(&self, index: usize) -> Option<CreatureId> {
         if index >= self.map.len() {
             return None;
@@ -45,8 +90,7 @@ impl CreatureMap {
         }
     }
 
-    fn allocate(&mut self) -> CreatureId {
-        let generation = get_generation();
+    fn allocate(&mut self, generation: u64) -> CreatureId {
         let index;
         if self.free.len() != 0 {
             index = self.free.pop().unwrap();
@@ -69,6 +113,11 @@ impl CreatureMap {
         match &self.map[id.get_index()] {
             Some(c) => {
                 if c.get_id() == id {
+                    if let Some(infection) = c.get_infection() {
+                        let caused = infection.caused;
+                        self.end_infection(caused);
+                    }
+                    self.ancestry.remove(id.get_uid());
                     self.free.push(id.get_index());
                     self.map[id.get_index()] = None;
                     true
@@ -131,9 +180,62 @@ impl CreatureMap {
         self.map[id.get_index()] = Some(creat);
     }
 
-    pub fn add_creature(&mut self, x: u32, y: u32, gene: Vec<Command>) -> CreatureId {
-        let id = self.allocate();
-        self.set_creature(id.clone(), Creature::new(id.clone(), x, y, gene));
+    pub fn add_creature(
+        &mut self,
+        x: u32,
+        y: u32,
+        gene: Vec<Command>,
+        traits: Traits,
+        parent: Option<&CreatureStats>,
+    ) -> CreatureId {
+        let id = self.allocate(get_generation());
+        let origin = Origin::new(parent, self.round);
+        self.ancestry.add(id.get_uid(), origin.parent, self.round);
+        let mut creature = Creature::new(id.clone(), x, y, gene, traits, origin);
+        // Keeps the species of its parent until the next clustering
+        if let Some(p) = parent {
+            creature.set_species(p.get_species());
+        }
+        self.set_creature(id.clone(), creature);
+        id
+    }
+
+    // The egg is part of the ancestry from the moment it's laid
+    pub fn lay_egg(
+        &mut self,
+        gene: Vec<Command>,
+        traits: Traits,
+        parent: &CreatureStats,
+        energy: u32,
+    ) -> Egg {
+        let uid = get_generation();
+        let origin = Origin::new(Some(parent), self.round);
+        self.ancestry.add(uid, origin.parent, self.round);
+        self.num_eggs += 1;
+
+        Egg {
+            uid,
+            hatch_round: self.round + parent.get_traits().incubation,
+            genes: gene,
+            traits,
+            origin,
+            species: parent.get_species(),
+            energy,
+        }
+    }
+
+    pub fn destroy_egg(&mut self, egg: &Egg) {
+        self.ancestry.remove(egg.uid);
+        self.num_eggs -= 1;
+    }
+
+    pub fn hatch(&mut self, x: u32, y: u32, egg: Egg) -> CreatureId {
+        let id = self.allocate(egg.uid);
+        self.num_eggs -= 1;
+        let mut creature = Creature::new(id.clone(), x, y, egg.genes, egg.traits, egg.origin);
+        creature.set_species(egg.species);
+        creature.set_energy(egg.energy);
+        self.set_creature(id.clone(), creature);
         id
     }
 }
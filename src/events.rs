@@ -0,0 +1,160 @@
+use crate::config::Config;
+use crate::creaturemap::{CreatureId, CreatureMap};
+use crate::parsing::arg;
+use crate::world::World;
+
+use rand::Rng;
+
+use std::fmt;
+
+pub const NUM_EVENT_KINDS: usize = 4;
+
+#[derive(Clone)]
+pub enum EventKind {
+    // Keeps the given percentage of the food
+    Famine(u32),
+    // Kills every creature and destroys every egg
+    Meteor,
+    // Adds food to every tile
+    FoodBoom(u32),
+    // Moves the creatures by the given offset
+    Migration(i64, i64),
+}
+
+// Something happening to every tile within radius of a point
+#[derive(Clone)]
+pub struct Event {
+    kind: EventKind,
+    x: u32,
+    y: u32,
+    radius: u32,
+}
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            EventKind::Famine(p) => write!(f, "Famine keeping {}% of the food", p)?,
+            EventKind::Meteor => write!(f, "Meteor strike")?,
+            EventKind::FoodBoom(a) => write!(f, "Food boom of {}", a)?,
+            EventKind::Migration(dx, dy) => write!(f, "Migration by ({}, {})", dx, dy)?,
+        }
+        write!(f, " at ({}, {}) radius {}", self.x, self.y, self.radius)
+    }
+}
+
+impl Event {
+    // Parses "<kind> <x> <y> <radius> [parameters]"
+    pub fn parse(args: &[&str]) -> Result<Event, String> {
+        if args.is_empty() {
+            return Err(String::from("missing event kind"));
+        }
+
+        let kind = match args[0] {
+            "famine" => EventKind::Famine(std::cmp::min(arg(args, 4)?, 100)),
+            "meteor" => EventKind::Meteor,
+            "boom" => EventKind::FoodBoom(arg(args, 4)?),
+            "migration" => EventKind::Migration(arg(args, 4)?, arg(args, 5)?),
+            _ => return Err(format!("unknown event '{}'", args[0])),
+        };
+
+        Ok(Event {
+            kind,
+            x: arg(args, 1)?,
+            y: arg(args, 2)?,
+            radius: arg(args, 3)?,
+        })
+    }
+
+    // Rolls each kind of event against its chance for this round
+    pub fn random_events(world: &World, config: &Config) -> Vec<Event> {
+        let mut rng = rand::thread_rng();
+        let (width, height) = world.get_size();
+        let mut events = Vec::new();
+
+        for kind in 0..NUM_EVENT_KINDS {
+            if rng.gen::<f32>() * 100.0 >= config.event_chances[kind] {
+                continue;
+            }
+
+            let radius = config.event_radius;
+            let range = radius as i64 * 2;
+            events.push(Event {
+                kind: match kind {
+                    0 => EventKind::Famine(0),
+                    1 => EventKind::Meteor,
+                    2 => EventKind::FoodBoom(100),
+                    _ => EventKind::Migration(
+                        rng.gen_range(-range, range + 1),
+                        rng.gen_range(-range, range + 1),
+                    ),
+                },
+                x: rng.gen_range(0, width),
+                y: rng.gen_range(0, height),
+                radius,
+            });
+        }
+
+        events
+    }
+
+    // Returns a description of what happened for the log
+    pub fn apply(&self, world: &mut World, creatures: &mut CreatureMap) -> String {
+        let tiles = world.get_tiles_in_radius(self.x, self.y, self.radius);
+        let mut affected = 0;
+
+        match self.kind {
+            EventKind::Famine(percent) => {
+                for (x, y) in tiles {
+                    let tile = world.get_tile_mut(x, y);
+                    tile.food = ((tile.food as u64 * percent as u64) / 100) as u32;
+                    tile.fibre = ((tile.fibre as u64 * percent as u64) / 100) as u32;
+                }
+            }
+            EventKind::FoodBoom(amount) => {
+                for (x, y) in tiles {
+                    world.get_tile_mut(x, y).food += amount;
+                }
+            }
+            EventKind::Meteor => {
+                for (x, y) in tiles {
+                    let tile = world.get_tile_mut(x, y);
+                    if let Some(egg) = tile.egg.take() {
+                        creatures.destroy_egg(&egg);
+                    }
+                    if let Some(id) = tile.creature.take() {
+                        tile.carcass += creatures.get_creature(id.clone()).unwrap().get_energy();
+                        creatures.deallocate(id);
+                        affected += 1;
+                    }
+                }
+            }
+            EventKind::Migration(dx, dy) => {
+                // Creatures are gathered first so one that lands further in the area isn't moved twice,
+                // those that can't land on a free tile stay where they are
+                let movers: Vec<(CreatureId, u32, u32)> = tiles
+                    .into_iter()
+                    .filter_map(|(x, y)| world.get_tile(x, y).creature.clone().map(|id| (id, x, y)))
+                    .collect();
+                for (id, x, y) in movers {
+                    let (want_x, want_y) = world.wrap_pos(x as i64 + dx, y as i64 + dy);
+                    if world.get_tile(want_x, want_y).is_blocked() {
+                        continue;
+                    }
+
+                    world.get_tile_mut(x, y).creature = None;
+                    world.get_tile_mut(want_x, want_y).creature = Some(id.clone());
+                    creatures
+                        .get_creature_mut(id)
+                        .unwrap()
+                        .set_pos(want_x, want_y);
+                    affected += 1;
+                }
+            }
+        }
+
+        match self.kind {
+            EventKind::Meteor => format!("{}, {} creatures killed", self, affected),
+            EventKind::Migration(_, _) => format!("{}, {} creatures moved", self, affected),
+            _ => self.to_string(),
+        }
+    }
+}
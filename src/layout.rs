@@ -0,0 +1,196 @@
+use crate::world::{Terrain, World};
+
+use std::fs;
+
+// Hand drawn world read from an image, one pixel per tile:
+// red is the initial food, green the terrain and blue the weight of the tile
+// when placing the initial creatures with the image spawn strategy
+pub struct Layout {
+    width: u32,
+    height: u32,
+    pixels: Vec<(u8, u8, u8)>,
+}
+impl Layout {
+    // Only PPM images(binary P6 or ascii P3) are supported
+    pub fn load(path: &str) -> Result<Layout, String> {
+        let content = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        Layout::parse_ppm(&content).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    fn parse_ppm(content: &[u8]) -> Result<Layout, String> {
+        // Reads the next whitespace separated token, '#' starts a comment
+        fn token(content: &[u8], pos: &mut usize) -> Result<String, String> {
+            while *pos < content.len() {
+                match content[*pos] {
+                    b'#' => {
+                        while *pos < content.len() && content[*pos] != b'\n' {
+                            *pos += 1;
+                        }
+                    }
+                    c if c.is_ascii_whitespace() => *pos += 1,
+                    _ => break,
+                }
+            }
+
+            let start = *pos;
+            while *pos < content.len() && !content[*pos].is_ascii_whitespace() {
+                *pos += 1;
+            }
+            if start == *pos {
+                return Err(String::from("unexpected end of file"));
+            }
+            Ok(String::from_utf8_lossy(&content[start..*pos]).into_owned())
+        }
+
+        fn number(content: &[u8], pos: &mut usize) -> Result<u32, String> {
+            let t = token(content, pos)?;
+            t.parse::<u32>()
+                .map_err(|_| format!("invalid number '{}'", t))
+        }
+
+        let mut pos = 0;
+        let magic = token(content, &mut pos)?;
+        if magic != "P6" && magic != "P3" {
+            return Err(String::from("not a PPM image(P3 or P6)"));
+        }
+
+        let width = number(content, &mut pos)?;
+        let height = number(content, &mut pos)?;
+        let max = number(content, &mut pos)?;
+        if width == 0 || height == 0 {
+            return Err(String::from("empty image"));
+        }
+        if max == 0 || max > 65535 {
+            return Err(format!("invalid maximum value {}", max));
+        }
+
+        // The world indexes its tiles with u32
+        let num_values = match width.checked_mul(height) {
+            Some(num_tiles) => num_tiles as usize * 3,
+            None => return Err(format!("image of {}x{} is too large", width, height)),
+        };
+        // Every value takes at least a byte, checked before the header decides what to allocate
+        if num_values > content.len() - pos {
+            return Err(String::from("image data is truncated"));
+        }
+        let mut values: Vec<u32> = Vec::with_capacity(num_values);
+
+        if magic == "P6" {
+            // A single whitespace separates the header from the data
+            pos += 1;
+            let size = if max < 256 { 1 } else { 2 };
+            let data = content.get(pos..pos + (num_values * size));
+            let data = data.ok_or_else(|| String::from("image data is truncated"))?;
+            for v in data.chunks(size) {
+                values.push(match size {
+                    1 => v[0] as u32,
+                    _ => ((v[0] as u32) << 8) | v[1] as u32,
+                });
+            }
+        } else {
+            for _ in 0..num_values {
+                values.push(number(content, &mut pos)?);
+            }
+        }
+
+        // Every channel is scaled to 0-255
+        let scale = |v: u32| ((std::cmp::min(v, max) * 255) / max) as u8;
+        let pixels = values
+            .chunks(3)
+            .map(|p| (scale(p[0]), scale(p[1]), scale(p[2])))
+            .collect();
+
+        Ok(Layout {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    // The world must have the size of the layout
+    pub fn apply(&self, world: &mut World) {
+        let mut spawn_weights = Vec::with_capacity(self.pixels.len());
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (r, g, b) = self.pixels[((y * self.width) + x) as usize];
+                let tile = world.get_tile_mut(x, y);
+                tile.food = r as u32;
+                tile.terrain = match g {
+                    0..=84 => Terrain::Desert,
+                    85..=169 => Terrain::Grassland,
+                    _ => Terrain::Forest,
+                };
+                spawn_weights.push(b);
+            }
+        }
+
+        // The image spawn strategy can't be used if the image has no blue at all
+        if spawn_weights.iter().any(|&w| w != 0) {
+            world.set_spawn_weights(spawn_weights);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(content: &[u8]) -> String {
+        Layout::parse_ppm(content).err().unwrap()
+    }
+
+    #[test]
+    fn parse_p3() {
+        let layout = Layout::parse_ppm(b"P3\n# two pixels\n2 1\n10\n10 0 5  0 10 0\n").unwrap();
+        assert_eq!(layout.get_size(), (2, 1));
+        assert_eq!(layout.pixels, vec![(255, 0, 127), (0, 255, 0)]);
+    }
+
+    #[test]
+    fn parse_p6() {
+        let mut content = b"P6 1 2 255\n".to_vec();
+        content.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+        let layout = Layout::parse_ppm(&content).unwrap();
+        assert_eq!(layout.get_size(), (1, 2));
+        assert_eq!(layout.pixels, vec![(1, 2, 3), (4, 5, 6)]);
+
+        // Values above 255 take two bytes
+        let mut content = b"P6 1 1 1000\n".to_vec();
+        content.extend_from_slice(&[3, 232, 0, 0, 1, 244]);
+        let layout = Layout::parse_ppm(&content).unwrap();
+        assert_eq!(layout.pixels, vec![(255, 0, 127)]);
+    }
+
+    #[test]
+    fn parse_truncated() {
+        let error = parse_error(b"P6 2 1 255\n\x01\x02\x03\x04");
+        assert_eq!(error, "image data is truncated");
+
+        let error = parse_error(b"P3 2 1 255\n1 2 3 4");
+        assert_eq!(error, "unexpected end of file");
+
+        // The header alone must not make the parser allocate a huge image
+        let error = parse_error(b"P6 20000 20000 255\n\x00");
+        assert_eq!(error, "image data is truncated");
+    }
+
+    #[test]
+    fn parse_bad_header() {
+        let error = parse_error(b"P5 1 1 255\n\x00");
+        assert_eq!(error, "not a PPM image(P3 or P6)");
+
+        let error = parse_error(b"P3 1 x 255\n");
+        assert_eq!(error, "invalid number 'x'");
+
+        let error = parse_error(b"P3 0 1 255\n");
+        assert_eq!(error, "empty image");
+
+        let error = parse_error(b"P6 70000 70000 255\n");
+        assert_eq!(error, "image of 70000x70000 is too large");
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+struct Node {
+    parent: Option<u64>,
+    birth_round: u32,
+    alive: bool,
+    children: Vec<u64>,
+}
+
+// Keeps the ancestry of the living creatures
+// Branches without living descendants are dropped and dead ancestors with a single
+// descendant line are merged into it so the store only grows with the actual tree
+pub struct Ancestry {
+    nodes: HashMap<u64, Node>,
+}
+impl Ancestry {
+    pub fn new() -> Ancestry {
+        Ancestry {
+            nodes: HashMap::new(),
+        }
+    }
+
+    pub fn get_num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn add(&mut self, uid: u64, parent: Option<u64>, birth_round: u32) {
+        if let Some(p) = parent {
+            self.nodes.get_mut(&p).unwrap().children.push(uid);
+        }
+
+        self.nodes.insert(
+            uid,
+            Node {
+                parent,
+                birth_round,
+                alive: true,
+                children: Vec::new(),
+            },
+        );
+    }
+
+    pub fn remove(&mut self, uid: u64) {
+        match self.nodes.get_mut(&uid) {
+            Some(node) => node.alive = false,
+            None => return,
+        }
+
+        let mut cur = uid;
+        loop {
+            let node = &self.nodes[&cur];
+            if node.alive || node.children.len() > 1 {
+                return;
+            }
+
+            let parent = node.parent;
+            let node = self.nodes.remove(&cur).unwrap();
+
+            if let Some(&child) = node.children.first() {
+                // Only one descendant line left, link it directly to the grand parent
+                self.nodes.get_mut(&child).unwrap().parent = parent;
+                if let Some(p) = parent {
+                    let siblings = &mut self.nodes.get_mut(&p).unwrap().children;
+                    let pos = siblings.iter().position(|&c| c == cur).unwrap();
+                    siblings[pos] = child;
+                }
+                return;
+            }
+
+            match parent {
+                Some(p) => {
+                    let siblings = &mut self.nodes.get_mut(&p).unwrap().children;
+                    siblings.retain(|&c| c != cur);
+                    cur = p;
+                }
+                None => return,
+            }
+        }
+    }
+
+    fn get_roots(&self) -> Vec<u64> {
+        let mut roots: Vec<u64> = self
+            .nodes
+            .iter()
+            .filter(|(_, n)| n.parent.is_none())
+            .map(|(uid, _)| *uid)
+            .collect();
+        roots.sort();
+        roots
+    }
+
+    fn get_branch_length(&self, uid: u64) -> u32 {
+        let node = &self.nodes[&uid];
+        match node.parent {
+            Some(p) => node.birth_round - self.nodes[&p].birth_round,
+            None => node.birth_round,
+        }
+    }
+
+    // Walks the tree with an explicit stack, a lineage can be as deep as the number of rounds
+    fn write_newick(&self, out: &mut String, root: u64) {
+        enum Step {
+            Enter(u64),
+            Separate,
+            Leave(u64),
+        }
+
+        let mut stack = vec![Step::Enter(root)];
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Enter(uid) => {
+                    let children = &self.nodes[&uid].children;
+                    stack.push(Step::Leave(uid));
+                    if !children.is_empty() {
+                        out.push('(');
+                        for (i, child) in children.iter().enumerate().rev() {
+                            stack.push(Step::Enter(*child));
+                            if i != 0 {
+                                stack.push(Step::Separate);
+                            }
+                        }
+                    }
+                }
+                Step::Separate => out.push(','),
+                Step::Leave(uid) => {
+                    if !self.nodes[&uid].children.is_empty() {
+                        out.push(')');
+                    }
+                    write!(out, "c{}:{}", uid, self.get_branch_length(uid)).unwrap();
+                }
+            }
+        }
+    }
+
+    // Every initial creature is a root so the trees are joined under an unnamed root
+    pub fn to_newick(&self) -> String {
+        let mut out = String::new();
+
+        out.push('(');
+        for (i, root) in self.get_roots().iter().enumerate() {
+            if i != 0 {
+                out.push(',');
+            }
+            self.write_newick(&mut out, *root);
+        }
+        out.push_str(");\n");
+
+        out
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph phylogeny {\n");
+
+        let mut uids: Vec<&u64> = self.nodes.keys().collect();
+        uids.sort();
+
+        for uid in uids {
+            let node = &self.nodes[uid];
+            writeln!(
+                out,
+                "    c{} [label=\"c{}\\nborn {}\"{}];",
+                uid,
+                uid,
+                node.birth_round,
+                if node.alive { ", style=filled" } else { "" }
+            )
+            .unwrap();
+            for child in &node.children {
+                writeln!(
+                    out,
+                    "    c{} -> c{} [label=\"{}\"];",
+                    uid,
+                    child,
+                    self.get_branch_length(*child)
+                )
+                .unwrap();
+            }
+        }
+
+        out.push_str("}\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_leaf() {
+        let mut ancestry = Ancestry::new();
+        ancestry.add(1, None, 0);
+        ancestry.add(2, Some(1), 5);
+        ancestry.add(3, Some(1), 7);
+
+        ancestry.remove(3);
+        assert_eq!(ancestry.get_num_nodes(), 2);
+        assert_eq!(ancestry.to_newick(), "((c2:5)c1:0);\n");
+    }
+
+    #[test]
+    fn remove_internal_node_with_single_child() {
+        let mut ancestry = Ancestry::new();
+        ancestry.add(1, None, 0);
+        ancestry.add(2, Some(1), 5);
+        ancestry.add(3, Some(2), 12);
+
+        // The dead parent is merged into its only child, which keeps its distance to the grand parent
+        ancestry.remove(2);
+        assert_eq!(ancestry.get_num_nodes(), 2);
+        assert_eq!(ancestry.to_newick(), "((c3:12)c1:0);\n");
+    }
+
+    #[test]
+    fn remove_subtree() {
+        let mut ancestry = Ancestry::new();
+        ancestry.add(1, None, 0);
+        ancestry.add(2, Some(1), 5);
+        ancestry.add(3, Some(2), 8);
+        ancestry.add(4, Some(2), 9);
+        ancestry.add(5, Some(1), 6);
+
+        // A dead node with two living lines is kept
+        ancestry.remove(2);
+        assert_eq!(ancestry.get_num_nodes(), 5);
+
+        ancestry.remove(3);
+        ancestry.remove(4);
+        assert_eq!(ancestry.get_num_nodes(), 2);
+        assert_eq!(ancestry.to_newick(), "((c5:6)c1:0);\n");
+
+        ancestry.remove(1);
+        ancestry.remove(5);
+        assert_eq!(ancestry.get_num_nodes(), 0);
+        assert_eq!(ancestry.to_newick(), "();\n");
+    }
+
+    #[test]
+    fn newick_of_deep_lineage() {
+        let mut ancestry = Ancestry::new();
+        ancestry.add(0, None, 0);
+        for uid in 1..100_000 {
+            ancestry.add(uid, Some(uid - 1), uid as u32);
+        }
+
+        let newick = ancestry.to_newick();
+        assert!(newick.starts_with(&format!("{}c99999:1)c99998:1)", "(".repeat(100_000))));
+        assert!(newick.ends_with(")c1:1)c0:0);\n"));
+    }
+}
@@ -1,15 +1,27 @@
+mod config;
 mod creature;
 mod creaturemap;
+mod events;
+mod layout;
+mod lineage;
+mod parsing;
 
 mod renderer;
+mod scenario;
+mod spawn;
+mod species;
+mod timeline;
 mod world;
-use creature::command::Command;
+mod worldgen;
+use config::Config;
 use creaturemap::{CreatureId, CreatureMap};
+use layout::Layout;
 use renderer::{DisplayTypes, Renderer};
+use scenario::Scenario;
+use species::SpeciesTracker;
+use timeline::Timeline;
 use world::World;
 
-use rand::Rng;
-
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::mouse::MouseButton;
@@ -21,35 +33,40 @@ struct Game<'texture> {
     creatures: CreatureMap,
     pub gfx: Renderer<'texture>,
     round: u32,
+    config: Config,
+    species: SpeciesTracker,
+    timeline: Timeline,
 }
 impl<'texture> Game<'texture> {
-    pub fn new(width: u32, height: u32, num_creatures: u32, sdl_ctx: &Sdl) -> Game {
-
-        let mut world = World::new(width, height);
+    pub fn new(
+        config: Config,
+        timeline: Timeline,
+        layout: Option<Layout>,
+        scenario: Option<Scenario>,
+        sdl_ctx: &Sdl,
+    ) -> Result<Game, String> {
+        let mut world = World::new(&config);
+        if let Some(layout) = layout {
+            layout.apply(&mut world);
+        }
         let mut creatures = CreatureMap::new();
-        let gfx = Renderer::new(&sdl_ctx, &world);
 
-        for _ in 0..num_creatures {
-            loop {
-                let x = rand::thread_rng().gen_range(0, width);
-                let y = rand::thread_rng().gen_range(0, height);
-
-                let tile = world.get_tile_mut(x, y);
-
-                if let None = tile.creature {
-                    let id = creatures.add_creature(x, y, vec![Command::Eat, Command::Reproduce]);
-                    tile.creature = Some(id);
-                    break;
-                }
-            }
+        match scenario {
+            Some(scenario) => scenario.apply(&mut world, &mut creatures, &config),
+            None => spawn::place_creatures(&mut world, &mut creatures, &config)?,
         }
 
-        Game {
+        let gfx = Renderer::new(&sdl_ctx, &world);
+
+        Ok(Game {
             world: world,
             creatures: creatures,
             gfx: gfx,
             round: 0,
-        }
+            config,
+            species: SpeciesTracker::new(),
+            timeline,
+        })
     }
 
     pub fn init(&mut self, tex_creat: &'texture TextureCreator<sdl2::video::WindowContext>) {
@@ -74,13 +91,55 @@ impl<'texture> Game<'texture> {
 
         println!("X: {} Y: {}", x, y);
         println!("{}", tile);
+        println!("Temperature: {:.1}", self.world.get_temperature(x, y));
         if let Some(c) = &tile.creature {
             let creat = self.creatures.get_creature(c.clone()).unwrap();
             println!("{}", creat);
+            if let Some(s) = self.species.get_species(creat.get_species()) {
+                println!("{}", s);
+            }
+        }
+    }
+
+    pub fn export_phylogeny(&self) {
+        let ancestry = self.creatures.get_ancestry();
+
+        for (path, content) in &[
+            ("phylogeny.nwk", ancestry.to_newick()),
+            ("phylogeny.dot", ancestry.to_dot()),
+        ] {
+            match std::fs::write(path, content) {
+                Ok(_) => println!("Phylogeny written to {}", path),
+                Err(e) => println!("Failed to write {}: {}", path, e),
+            }
+        }
+        println!("{} nodes in the ancestry tree", ancestry.get_num_nodes());
+    }
+
+    fn run_events(&mut self) {
+        let mut happening: Vec<events::Event> = self
+            .config
+            .events
+            .iter()
+            .filter(|(round, _)| *round == self.round)
+            .map(|(_, e)| e.clone())
+            .collect();
+        happening.extend(events::Event::random_events(&self.world, &self.config));
+
+        for event in happening {
+            let log = event.apply(&mut self.world, &mut self.creatures);
+            println!("Round {}, event: {}", self.round, log);
+        }
+
+        for intervention in self.timeline.get_interventions(self.round) {
+            let log = intervention.apply(&mut self.world, &mut self.creatures, &mut self.config);
+            println!("Round {}, timeline: {}", self.round, log);
         }
     }
 
     pub fn simulate(&mut self) -> bool {
+        self.run_events();
+
         let mut active_creatures: Vec<CreatureId> = Vec::new();
 
         for i in 0..self.creatures.get_num() {
@@ -88,29 +147,75 @@ impl<'texture> Game<'texture> {
                 active_creatures.push(c);
             }
         }
+        let (mut trophic_sum, mut trophic_num) = (0.0, 0);
+        let mut infected = 0;
+        for id in &active_creatures {
+            let c = self.creatures.get_creature(id.clone()).unwrap();
+            if let Some(t) = c.get_trophic_level() {
+                trophic_sum += t;
+                trophic_num += 1;
+            }
+            if c.get_infection().is_some() {
+                infected += 1;
+            }
+        }
+
         println!(
-            "Round {}, number of active creatures: {}",
+            "Round {}, number of active creatures: {}, mean trophic level: {:.2}",
             self.round,
-            active_creatures.len()
+            active_creatures.len(),
+            if trophic_num == 0 {
+                1.0
+            } else {
+                trophic_sum / trophic_num as f32
+            }
         );
 
+        if infected != 0 {
+            match self.creatures.get_r0() {
+                Some(r0) => println!("Infected creatures: {}, R0: {:.2}", infected, r0),
+                None => println!("Infected creatures: {}, R0: unknown", infected),
+            }
+        }
+
         debug_assert_eq!(
             active_creatures.len() as u32,
             self.world.get_num_creatures() as u32
         );
 
-        if active_creatures.len() == 0 {
+        // Eggs still waiting to hatch keep the population alive
+        if self.creatures.get_population() == 0 {
             println!("Every creature died at round {}", self.round);
             return false;
         }
 
+        if self.config.species_interval != 0 && self.round % self.config.species_interval == 0 {
+            self.species
+                .cluster(&mut self.creatures, self.round, &self.config);
+            println!(
+                "Round {}, number of living species: {}",
+                self.round,
+                self.species.get_num_alive()
+            );
+        }
+
+        self.creatures.set_round(self.round);
+
         for id in &active_creatures {
             if let Some(mut c) = self.creatures.move_creature(id.clone()) {
-                if c.simulate(&mut self.world, &mut self.creatures) {
-                    self.creatures.set_creature(c.get_id(), c);
+                let alive = c.simulate(&mut self.world, &mut self.creatures, &self.config);
+                self.creatures.set_creature(c.get_id(), c);
+                if !alive {
+                    self.creatures.deallocate(id.clone());
                 }
             }
         }
+        for (x, y, egg) in self.world.take_hatching_eggs(self.round) {
+            let id = self.creatures.hatch(x, y, egg);
+            self.world.get_tile_mut(x, y).creature = Some(id);
+        }
+
+        self.world.update(&self.config, self.round);
         self.round += 1;
 
         active_creatures.clear();
@@ -119,6 +224,64 @@ impl<'texture> Game<'texture> {
 }
 
 fn main() {
+    let mut config = match std::env::args().nth(1) {
+        Some(path) => match Config::load(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                println!("Failed to load config: {}", e);
+                return;
+            }
+        },
+        None => Config::default(),
+    };
+
+    let layout = match &config.layout {
+        Some(path) => match Layout::load(path) {
+            Ok(layout) => {
+                let (width, height) = layout.get_size();
+                config.width = width;
+                config.height = height;
+                Some(layout)
+            }
+            Err(e) => {
+                println!("Failed to load layout: {}", e);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let scenario = match &config.scenario {
+        Some(_) if config.layout.is_some() => {
+            println!("A layout and a scenario can't be used together");
+            return;
+        }
+        Some(path) => match Scenario::load(path) {
+            Ok(scenario) => {
+                let (width, height) = scenario.get_size();
+                config.width = width;
+                config.height = height;
+                Some(scenario)
+            }
+            Err(e) => {
+                println!("Failed to load scenario: {}", e);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let timeline = match &config.timeline {
+        Some(path) => match Timeline::load(path) {
+            Ok(timeline) => timeline,
+            Err(e) => {
+                println!("Failed to load timeline: {}", e);
+                return;
+            }
+        },
+        None => Timeline::new(),
+    };
+
     let sdl_context = sdl2::init().unwrap();
     let tex_creat;
     let mut display_type = DisplayTypes::FoodType;
@@ -126,7 +289,13 @@ fn main() {
     let mut event_pump = sdl_context.event_pump().unwrap();
 
     {
-        let mut g = Game::new(800, 600, 500, &sdl_context);
+        let mut g = match Game::new(config, timeline, layout, scenario, &sdl_context) {
+            Ok(g) => g,
+            Err(e) => {
+                println!("Failed to place the creatures: {}", e);
+                return;
+            }
+        };
         let main_canvas = g.gfx.get_canvas();
 
         // TextureCreator had to be made outside of the scope of Game as it's borrowed for the lifetime of Texture
@@ -157,6 +326,14 @@ fn main() {
                         Keycode::Space => paused = !paused,
                         Keycode::Kp1 => display_type = DisplayTypes::FoodType,
                         Keycode::Kp2 => display_type = DisplayTypes::GeneComplexity,
+                        Keycode::Kp3 => display_type = DisplayTypes::Age,
+                        Keycode::Kp4 => display_type = DisplayTypes::Species,
+                        Keycode::Kp5 => display_type = DisplayTypes::TrophicLevel,
+                        Keycode::Kp6 => display_type = DisplayTypes::Pheromones,
+                        Keycode::Kp7 => display_type = DisplayTypes::Disease,
+                        Keycode::Kp8 => display_type = DisplayTypes::Temperature,
+                        Keycode::L => g.export_phylogeny(),
+                        Keycode::S => println!("{}", g.species),
                         _ => {}
                     },
                     Event::MouseButtonDown {
@@ -0,0 +1,19 @@
+// Helpers shared by the text files: config, timeline, scenario and genome bank
+
+// Removes the comment started by '#' from a line
+pub fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(pos) => &line[..pos],
+        None => line,
+    }
+}
+
+// Parses the argument at index i of a whitespace separated line, args[0] being its name
+pub fn arg<T: std::str::FromStr>(args: &[&str], i: usize) -> Result<T, String> {
+    match args.get(i) {
+        Some(v) => v
+            .parse::<T>()
+            .map_err(|_| format!("invalid argument '{}'", v)),
+        None => Err(format!("missing argument {} for {}", i, args[0])),
+    }
+}
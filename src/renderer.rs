@@ -1,5 +1,5 @@
 use crate::creature::command::NUM_MAX_GENES;
-use crate::creature::CreatureType;
+use crate::creature::{Creature, CreatureType};
 use crate::creaturemap::CreatureMap;
 use crate::world::World;
 
@@ -9,9 +9,16 @@ use sdl2::rect::Rect;
 use sdl2::render::TextureCreator;
 use sdl2::Sdl;
 
+#[derive(PartialEq)]
 pub enum DisplayTypes {
     FoodType,
     GeneComplexity,
+    Age,
+    Species,
+    TrophicLevel,
+    Pheromones,
+    Disease,
+    Temperature,
 }
 
 pub struct Renderer<'texture> {
@@ -77,106 +84,113 @@ impl<'texture> Renderer<'texture> {
         );
     }
 
+    // Blue to red over an arbitrary range of temperatures
+    fn temperature_color(t: f32) -> (u8, u8, u8) {
+        const MIN_TEMPERATURE: f32 = -10.0;
+        const MAX_TEMPERATURE: f32 = 40.0;
+        let hot = ((t - MIN_TEMPERATURE) * 255.0 / (MAX_TEMPERATURE - MIN_TEMPERATURE))
+            .clamp(0.0, 255.0) as u8;
+        (hot, 0, 255 - hot)
+    }
+
+    fn creature_color(
+        c: &Creature,
+        disp: &DisplayTypes,
+        gene_colors: &[u8; NUM_MAX_GENES],
+    ) -> (u8, u8, u8) {
+        match disp {
+            DisplayTypes::FoodType => match c.get_type() {
+                CreatureType::Carnivore => (255, 0, 0),
+                CreatureType::Herbivore => (0, 255, 0),
+                CreatureType::Omnivore => (255, 255, 0),
+            },
+            DisplayTypes::GeneComplexity => (0, gene_colors[c.get_num_genes() - 1], 0),
+            DisplayTypes::Age => {
+                // Immortal creatures are scaled against an arbitrary age
+                const AGE_SCALE: u64 = 1000;
+                let lifespan = match c.get_traits().lifespan {
+                    0 => AGE_SCALE,
+                    l => l as u64,
+                };
+                let old = std::cmp::min((c.get_age() as u64 * 255) / lifespan, 255) as u8;
+                (old, 255 - old, 0)
+            }
+            DisplayTypes::TrophicLevel => match c.get_trophic_level() {
+                Some(t) => {
+                    let meat = ((t - 1.0) * 255.0) as u8;
+                    (meat, 255 - meat, 0)
+                }
+                None => (128, 128, 128),
+            },
+            DisplayTypes::Pheromones => (255, 255, 255),
+            DisplayTypes::Disease => match c.get_infection() {
+                Some(_) => (255, 0, 0),
+                None => (0, 55 + (c.get_traits().immunity * 2) as u8, 0),
+            },
+            DisplayTypes::Temperature => {
+                let (r, _, b) = Renderer::temperature_color(c.get_traits().thermal_optimum);
+                (r, 255, b)
+            }
+            DisplayTypes::Species => {
+                // Scrambles the id so close ids get distinct colors
+                let hash = c.get_species().wrapping_mul(2_654_435_761);
+                (
+                    (hash >> 24) as u8 | 0x40,
+                    (hash >> 16) as u8 | 0x40,
+                    (hash >> 8) as u8 | 0x40,
+                )
+            }
+        }
+    }
+
     pub fn update(&mut self, world: &World, creatures: &CreatureMap, disp: &DisplayTypes) {
         let (width, height) = (self.width, self.height);
 
-        match disp {
-            DisplayTypes::FoodType => {
-                self.texture
-                    .as_mut()
-                    .unwrap()
-                    .with_lock(None, |buffer: &mut [u8], _pitch: usize| {
-                        for y in 0..height {
-                            for x in 0..width {
-                                let tile = world.get_tile(x, y);
-                                if let None = tile.creature {
-                                    buffer[((y * width) + x) as usize * 4] = 255;
-                                    buffer[(((y * width) + x) as usize * 4) + 1] = {
-                                        if tile.food >= 255 {
-                                            255
-                                        } else {
-                                            tile.food as u8
-                                        }
-                                    };
-                                    buffer[(((y * width) + x) as usize * 4) + 2] = 0;
-                                    buffer[(((y * width) + x) as usize * 4) + 3] = 0;
-                                } else {
-                                    let c = creatures
-                                        .get_creature(tile.creature.clone().unwrap())
-                                        .unwrap();
-                                    let (r, g, b): (u8, u8, u8);
-                                    match c.get_type() {
-                                        CreatureType::Carnivore => {
-                                            r = 255;
-                                            g = 0;
-                                            b = 0;
-                                        }
-                                        CreatureType::Herbivore => {
-                                            r = 0;
-                                            g = 255;
-                                            b = 0;
-                                        }
-                                        CreatureType::Omnivore => {
-                                            r = 255;
-                                            g = 255;
-                                            b = 0;
-                                        }
+        let colors = self.gene_colors;
+
+        self.texture
+            .as_mut()
+            .unwrap()
+            .with_lock(None, |buffer: &mut [u8], _pitch: usize| {
+                for y in 0..height {
+                    for x in 0..width {
+                        let tile = world.get_tile(x, y);
+                        let (r, g, b) = match &tile.creature {
+                            None if disp == &DisplayTypes::Pheromones => {
+                                // First 3 layers are shown as red, green and blue
+                                let mut rgb = [0u8; 3];
+                                for (layer, c) in rgb.iter_mut().enumerate() {
+                                    if layer < world.get_num_pheromones() {
+                                        *c = world.get_pheromone(layer, x, y).min(255.0) as u8;
                                     }
-
-                                    buffer[((y * width) + x) as usize * 4] = 255;
-                                    buffer[(((y * width) + x) as usize * 4) + 1] = b;
-                                    buffer[(((y * width) + x) as usize * 4) + 2] = g;
-                                    buffer[(((y * width) + x) as usize * 4) + 3] = r;
                                 }
+                                (rgb[0], rgb[1], rgb[2])
                             }
-
-                        }
-                    })
-                    .unwrap();
-            }
-            DisplayTypes::GeneComplexity => {
-                let colors = self.gene_colors;
-                self.texture
-                    .as_mut()
-                    .unwrap()
-                    .with_lock(None, |buffer: &mut [u8], _pitch: usize| {
-                        for y in 0..height {
-                            for x in 0..width {
-                                let tile = world.get_tile(x, y);
-                                if let None = tile.creature {
-                                    buffer[((y * width) + x) as usize * 4] = 255;
-                                    buffer[(((y * width) + x) as usize * 4) + 1] = {
-                                        if tile.food >= 255 {
-                                            255
-                                        } else {
-                                            tile.food as u8
-                                        }
-                                    };
-                                    buffer[(((y * width) + x) as usize * 4) + 2] = 0;
-                                    buffer[(((y * width) + x) as usize * 4) + 3] = 0;
-                                } else {
-                                    let c = creatures
-                                        .get_creature(tile.creature.clone().unwrap())
-                                        .unwrap();
-                                    let (r, g, b): (u8, u8, u8);
-                                    r = 0;
-                                    g = colors[c.get_num_genes() - 1];
-                                    b = 0;
-
-                                    buffer[((y * width) + x) as usize * 4] = 255;
-                                    buffer[(((y * width) + x) as usize * 4) + 1] = b;
-                                    buffer[(((y * width) + x) as usize * 4) + 2] = g;
-                                    buffer[(((y * width) + x) as usize * 4) + 3] = r;
-                                }
-
+                            None if disp == &DisplayTypes::Temperature => {
+                                Renderer::temperature_color(world.get_temperature(x, y))
                             }
-
-                        }
-                    })
-                    .unwrap();
-            }
-        }
-
+                            None if tile.wall => (100, 100, 100),
+                            None if tile.egg.is_some() => (200, 200, 200),
+                            None => (
+                                std::cmp::min(tile.carcass / 2, 128) as u8,
+                                std::cmp::min(tile.fibre, 255) as u8,
+                                std::cmp::min(tile.food, 255) as u8,
+                            ),
+                            Some(id) => {
+                                let c = creatures.get_creature(id.clone()).unwrap();
+                                Renderer::creature_color(c, disp, &colors)
+                            }
+                        };
+
+                        let offset = ((y * width) + x) as usize * 4;
+                        buffer[offset] = 255;
+                        buffer[offset + 1] = b;
+                        buffer[offset + 2] = g;
+                        buffer[offset + 3] = r;
+                    }
+                }
+            })
+            .unwrap();
 
         let rect = Rect::new(0, 0, width, height);
         self.canvas
@@ -0,0 +1,277 @@
+use crate::config::Config;
+use crate::creature::command::Command;
+use crate::creature::traits::Traits;
+use crate::creaturemap::CreatureMap;
+use crate::parsing;
+use crate::world::{Terrain, World};
+
+use std::collections::HashMap;
+use std::fs;
+
+// Small hand written world, ie:
+//   genome herbivore Eat,Reproduce
+//   genome predator Attack,Move,Reproduce
+//   creature h herbivore
+//   creature p predator 300
+//   map
+//   ..#..
+//   .ph99
+//   ..h9.
+// Before the map each line is a declaration, '#' starts a comment:
+//   genome <name> <genes>: names the comma separated list of 1 to 16 genes making up the rest of the line
+//   creature <char> <genome> [energy]: the character places a creature with that genome
+// After the map line every line is a row of tiles and the world takes the size of the grid:
+//   '.' is an empty tile, '#' a wall, '0' to '9' a tile with a hundred times that food
+pub struct Scenario {
+    width: u32,
+    height: u32,
+    rows: Vec<Vec<char>>,
+    creatures: HashMap<char, (Vec<Command>, Option<u32>)>,
+}
+impl Scenario {
+    pub fn load(path: &str) -> Result<Scenario, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Scenario::parse(&content).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn parse(content: &str) -> Result<Scenario, String> {
+        let mut genomes: HashMap<String, Vec<Command>> = HashMap::new();
+        let mut creatures = HashMap::new();
+        let mut lines = content.lines().enumerate();
+
+        for (num, line) in &mut lines {
+            let line = parsing::strip_comment(line);
+
+            let args: Vec<&str> = line.split_whitespace().collect();
+            match args.as_slice() {
+                [] => continue,
+                ["map"] => break,
+                // The genes are the rest of the line so they can be separated by ", "
+                ["genome", name, genes @ ..] if !genes.is_empty() => {
+                    let genes = Command::parse_genes(&genes.join(" "))
+                        .map_err(|e| format!("{}: {}", num + 1, e))?;
+                    genomes.insert(name.to_string(), genes);
+                }
+                ["creature", symbol, genome, energy @ ..] if energy.len() <= 1 => {
+                    let mut chars = symbol.chars();
+                    let symbol = match (chars.next(), chars.next()) {
+                        (Some(c), None) if c != '.' && c != '#' && !c.is_ascii_digit() => c,
+                        _ => return Err(format!("{}: invalid creature '{}'", num + 1, symbol)),
+                    };
+                    let genes = genomes
+                        .get(*genome)
+                        .ok_or_else(|| format!("{}: unknown genome '{}'", num + 1, genome))?;
+                    // A creature without energy would die before its first round
+                    let energy = match energy.first() {
+                        Some(e) => match e.parse::<u32>() {
+                            Ok(energy) if energy != 0 => Some(energy),
+                            _ => return Err(format!("{}: invalid energy '{}'", num + 1, e)),
+                        },
+                        None => None,
+                    };
+                    creatures.insert(symbol, (genes.clone(), energy));
+                }
+                _ => return Err(format!("{}: invalid declaration", num + 1)),
+            }
+        }
+
+        let mut rows: Vec<Vec<char>> = Vec::new();
+        for (num, line) in lines {
+            let row: Vec<char> = line.trim_end().chars().collect();
+            if row.is_empty() {
+                continue;
+            }
+            if let Some(c) = row
+                .iter()
+                .find(|c| !matches!(c, '.' | '#' | '0'..='9') && !creatures.contains_key(c))
+            {
+                return Err(format!("{}: unknown tile '{}'", num + 1, c));
+            }
+            if !rows.is_empty() && row.len() != rows[0].len() {
+                return Err(format!("{}: every row must have the same length", num + 1));
+            }
+            rows.push(row);
+        }
+
+        if rows.is_empty() {
+            return Err(String::from("missing map"));
+        }
+
+        Ok(Scenario {
+            width: rows[0].len() as u32,
+            height: rows.len() as u32,
+            rows,
+            creatures,
+        })
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    // The world must have the size of the scenario
+    pub fn apply(&self, world: &mut World, creatures: &mut CreatureMap, config: &Config) {
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                let (x, y) = (x as u32, y as u32);
+                // Only the map decides what is on a tile, whatever the generator put there is cleared
+                let tile = world.get_tile_mut(x, y);
+                tile.terrain = Terrain::Grassland;
+                tile.food = 0;
+                tile.fibre = 0;
+                tile.carcass = 0;
+                tile.regrowth = 0;
+                tile.capacity = None;
+                tile.wall = false;
+                tile.creature = None;
+                tile.egg = None;
+
+                match c {
+                    '.' => {}
+                    '#' => tile.wall = true,
+                    '0'..='9' => tile.food = c.to_digit(10).unwrap() * 100,
+                    _ => {
+                        let (genes, energy) = &self.creatures[c];
+                        let id =
+                            creatures.add_creature(x, y, genes.clone(), Traits::new(config), None);
+                        if let Some(energy) = energy {
+                            creatures
+                                .get_creature_mut(id.clone())
+                                .unwrap()
+                                .set_energy(*energy);
+                        }
+                        tile.creature = Some(id);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WorldGenerator;
+    use crate::creature::command::NUM_MAX_GENES;
+
+    const SCENARIO: &str = "\
+# A predator next to two herbivores that have food on their other side, under a wall
+genome herbivore Eat, Reproduce
+genome predator Attack,Move,Reproduce
+creature h herbivore
+creature p predator 300
+map
+..#..
+.ph99
+..h9.
+";
+
+    fn parse_error(content: &str) -> String {
+        Scenario::parse(content).err().unwrap()
+    }
+
+    #[test]
+    fn parse_valid_map() {
+        let scenario = Scenario::parse(SCENARIO).unwrap();
+        assert_eq!(scenario.get_size(), (5, 3));
+
+        let (genes, energy) = &scenario.creatures[&'h'];
+        assert!(*genes == vec![Command::Eat, Command::Reproduce]);
+        assert_eq!(*energy, None);
+        let (genes, energy) = &scenario.creatures[&'p'];
+        assert!(*genes == vec![Command::Attack, Command::Move, Command::Reproduce]);
+        assert_eq!(*energy, Some(300));
+    }
+
+    #[test]
+    fn parse_unknown_tile() {
+        let error = parse_error("map\n...\n.x.\n");
+        assert_eq!(error, "3: unknown tile 'x'");
+
+        // A creature declared after the map is just an unknown tile
+        let error = parse_error("map\n...\ngenome herbivore Eat\n");
+        assert_eq!(error, "3: unknown tile 'g'");
+    }
+
+    #[test]
+    fn parse_unknown_genome() {
+        let error = parse_error("creature h herbivore\nmap\n..h\n");
+        assert_eq!(error, "1: unknown genome 'herbivore'");
+    }
+
+    #[test]
+    fn parse_ragged_rows() {
+        let error = parse_error("map\n...\n....\n");
+        assert_eq!(error, "3: every row must have the same length");
+    }
+
+    #[test]
+    fn parse_bad_energy() {
+        let error = parse_error("genome herbivore Eat\ncreature h herbivore 0\nmap\nh\n");
+        assert_eq!(error, "2: invalid energy '0'");
+
+        let error = parse_error("genome herbivore Eat\ncreature h herbivore lots\nmap\nh\n");
+        assert_eq!(error, "2: invalid energy 'lots'");
+    }
+
+    #[test]
+    fn parse_bad_genes() {
+        let error = parse_error("genome herbivore Eat,Fly\nmap\n.\n");
+        assert_eq!(error, "1: unknown command 'Fly'");
+
+        let error = parse_error("genome herbivore ,\nmap\n.\n");
+        assert_eq!(error, "1: empty genome");
+
+        let genes = vec!["Eat"; NUM_MAX_GENES + 1].join(",");
+        let error = parse_error(&format!("genome herbivore {}\nmap\n.\n", genes));
+        assert!(error.starts_with("1: a genome has at most"));
+    }
+
+    #[test]
+    fn apply_places_creatures_and_terrain() {
+        let scenario = Scenario::parse(SCENARIO).unwrap();
+        // The generated food, terrain, fibre and regrowth must all be replaced by the map
+        let config = Config {
+            width: 5,
+            height: 3,
+            fibre_initial: 50,
+            world_generator: WorldGenerator::Noise,
+            world_seed: Some(1),
+            ..Config::default()
+        };
+        let mut world = World::new(&config);
+        let mut creatures = CreatureMap::new();
+
+        scenario.apply(&mut world, &mut creatures, &config);
+        assert_eq!(creatures.get_population(), 3);
+
+        for y in 0..3 {
+            for x in 0..5 {
+                let tile = world.get_tile(x, y);
+                assert!(tile.terrain == Terrain::Grassland);
+                assert_eq!((tile.fibre, tile.carcass, tile.regrowth), (0, 0, 0));
+                assert_eq!(tile.capacity, None);
+            }
+        }
+
+        assert!(world.get_tile(2, 0).wall);
+        assert!(!world.get_tile(0, 0).wall);
+        assert_eq!(world.get_tile(0, 0).food, 0);
+        assert_eq!(world.get_tile(3, 1).food, 900);
+        assert_eq!(world.get_tile(3, 2).food, 900);
+
+        let predator = world.get_tile(1, 1).creature.clone().unwrap();
+        let predator = creatures.get_creature(predator).unwrap();
+        assert_eq!(predator.get_pos(), (1, 1));
+        assert_eq!(predator.get_energy(), 300);
+        assert!(*predator.get_genes() == vec![Command::Attack, Command::Move, Command::Reproduce]);
+
+        for (x, y) in &[(2, 1), (2, 2)] {
+            let herbivore = world.get_tile(*x, *y).creature.clone().unwrap();
+            let herbivore = creatures.get_creature(herbivore).unwrap();
+            assert_eq!(herbivore.get_pos(), (*x, *y));
+            assert!(*herbivore.get_genes() == vec![Command::Eat, Command::Reproduce]);
+        }
+        assert!(world.get_tile(0, 1).creature.is_none());
+    }
+}
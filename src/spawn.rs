@@ -0,0 +1,181 @@
+use crate::config::{Config, SpawnStrategy};
+use crate::creature::command::Command;
+use crate::creature::traits::Traits;
+use crate::creaturemap::CreatureMap;
+use crate::parsing;
+use crate::world::World;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use std::collections::HashSet;
+use std::fs;
+
+// Loads a file with one comma separated list of genes per line, '#' starts a comment
+pub fn load_genome_bank(path: &str) -> Result<Vec<Vec<Command>>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut genomes = Vec::new();
+
+    for (num, line) in content.lines().enumerate() {
+        let line = parsing::strip_comment(line).trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let genes =
+            Command::parse_genes(line).map_err(|e| format!("{}:{}: {}", path, num + 1, e))?;
+        genomes.push(genes);
+    }
+
+    if genomes.is_empty() {
+        return Err(format!("{}: no genome", path));
+    }
+    Ok(genomes)
+}
+
+// Picks count tiles out of the candidates, fails if there aren't enough
+fn pick_tiles(
+    mut candidates: Vec<(u32, u32)>,
+    count: usize,
+    place: &str,
+) -> Result<Vec<(u32, u32)>, String> {
+    if candidates.len() < count {
+        return Err(format!(
+            "{} only has {} free tiles for {} creatures",
+            place,
+            candidates.len(),
+            count
+        ));
+    }
+
+    candidates.shuffle(&mut rand::thread_rng());
+    candidates.truncate(count);
+    Ok(candidates)
+}
+
+// Splits count as evenly as possible into parts
+fn split_count(count: usize, parts: usize) -> impl Iterator<Item = usize> {
+    (0..parts).map(move |i| (count / parts) + if i < count % parts { 1 } else { 0 })
+}
+
+// Places the initial creatures according to the spawn strategy, each one gets a genome from the bank
+pub fn place_creatures(
+    world: &mut World,
+    creatures: &mut CreatureMap,
+    config: &Config,
+) -> Result<(), String> {
+    let genomes = match &config.genome_bank {
+        Some(path) => load_genome_bank(path)?,
+        None => vec![vec![Command::Eat, Command::Reproduce]],
+    };
+
+    let (width, height) = world.get_size();
+    let count = config.num_creatures as usize;
+    let mut rng = rand::thread_rng();
+
+    let free: Vec<(u32, u32)> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|(x, y)| !world.get_tile(*x, *y).is_blocked())
+        .collect();
+    if free.len() < count {
+        return Err(format!(
+            "the world only has {} free tiles for {} creatures",
+            free.len(),
+            count
+        ));
+    }
+
+    // Position and index of the genome of every creature
+    let mut placements: Vec<(u32, u32, usize)> = Vec::with_capacity(count);
+
+    match config.spawn_strategy {
+        SpawnStrategy::Uniform => {
+            for (x, y) in pick_tiles(free, count, "the world")? {
+                placements.push((x, y, rng.gen_range(0, genomes.len())));
+            }
+        }
+        SpawnStrategy::Clustered => {
+            // Each group shares a genome
+            let mut used: HashSet<(u32, u32)> = HashSet::new();
+            let num_clusters = std::cmp::max(config.spawn_clusters, 1) as usize;
+
+            for (i, size) in split_count(count, num_clusters).enumerate() {
+                let (cx, cy) = free[rng.gen_range(0, free.len())];
+                let candidates = world
+                    .get_tiles_in_radius(cx, cy, config.spawn_cluster_radius)
+                    .into_iter()
+                    .filter(|(x, y)| !world.get_tile(*x, *y).is_blocked())
+                    .filter(|pos| !used.contains(pos))
+                    .collect();
+                let place = format!("the cluster at ({}, {})", cx, cy);
+                for (x, y) in pick_tiles(candidates, size, &place)? {
+                    used.insert((x, y));
+                    placements.push((x, y, i % genomes.len()));
+                }
+            }
+        }
+        SpawnStrategy::Regions => {
+            // The world is cut in vertical bands, one for each genome
+            let num_regions = std::cmp::min(genomes.len(), width as usize);
+            for (i, size) in split_count(count, num_regions).enumerate() {
+                let (start, end) = (
+                    (i * width as usize / num_regions) as u32,
+                    ((i + 1) * width as usize / num_regions) as u32,
+                );
+                let candidates = free
+                    .iter()
+                    .filter(|(x, _)| *x >= start && *x < end)
+                    .cloned()
+                    .collect();
+                let place = format!("the region of genome {}", i + 1);
+                for (x, y) in pick_tiles(candidates, size, &place)? {
+                    placements.push((x, y, i));
+                }
+            }
+        }
+        SpawnStrategy::Image => {
+            // Weighted sampling without replacement, a random key is drawn for each tile
+            // and the tiles with the highest keys are picked
+            let mut keyed: Vec<(f32, (u32, u32))> = Vec::with_capacity(free.len());
+            for (x, y) in free {
+                let weight = match world.get_spawn_weight(x, y) {
+                    Some(w) => w,
+                    None => {
+                        return Err(String::from("the image strategy needs a layout with blue"))
+                    }
+                };
+                if weight != 0 {
+                    let key = rng.gen::<f32>().powf(1.0 / weight as f32);
+                    keyed.push((key, (x, y)));
+                }
+            }
+
+            if keyed.len() < count {
+                return Err(format!(
+                    "the layout only has {} free tiles with blue for {} creatures",
+                    keyed.len(),
+                    count
+                ));
+            }
+
+            keyed.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+            for (_, (x, y)) in keyed.into_iter().take(count) {
+                placements.push((x, y, rng.gen_range(0, genomes.len())));
+            }
+        }
+    }
+
+    for (x, y, genome) in placements {
+        let id = creatures.add_creature(x, y, genomes[genome].clone(), Traits::new(config), None);
+        if rng.gen_range(0, 100) < config.disease_initial {
+            creatures
+                .get_creature_mut(id.clone())
+                .unwrap()
+                .infect(config);
+        }
+        world.get_tile_mut(x, y).creature = Some(id);
+    }
+
+    Ok(())
+}
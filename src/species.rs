@@ -0,0 +1,158 @@
+use crate::config::Config;
+use crate::creature::command::Command;
+use crate::creaturemap::CreatureMap;
+
+use std::collections::HashMap;
+use std::fmt;
+
+// Levenshtein distance between two gene lists
+pub fn genome_distance(a: &[Command], b: &[Command]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur: Vec<usize> = vec![0; b.len() + 1];
+
+    for (i, ga) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, gb) in b.iter().enumerate() {
+            let substitution = prev[j] + if ga == gb { 0 } else { 1 };
+            cur[j + 1] = std::cmp::min(substitution, std::cmp::min(prev[j + 1], cur[j]) + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev[b.len()]
+}
+
+pub struct Species {
+    id: u32,
+    // Genome the members are compared against, cleared on extinction
+    representative: Vec<Command>,
+    size: u32,
+    birth_round: u32,
+    extinction_round: Option<u32>,
+}
+impl fmt::Display for Species {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Species {}: Size: {} Born: {}",
+            self.id, self.size, self.birth_round
+        )?;
+        if let Some(r) = self.extinction_round {
+            write!(f, " Extinct: {}", r)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct SpeciesTracker {
+    species: Vec<Species>,
+}
+impl SpeciesTracker {
+    pub fn new() -> SpeciesTracker {
+        SpeciesTracker {
+            species: Vec::new(),
+        }
+    }
+
+    // Species ids start at 1, 0 is for creatures that were never clustered
+    pub fn get_species(&self, id: u32) -> Option<&Species> {
+        if id == 0 {
+            return None;
+        }
+        self.species.get((id - 1) as usize)
+    }
+
+    pub fn get_num_alive(&self) -> usize {
+        self.species
+            .iter()
+            .filter(|s| s.extinction_round.is_none())
+            .count()
+    }
+
+    // Assigns every creature to the closest living species if it's within the threshold
+    // or founds a new species, species left without members go extinct
+    pub fn cluster(&mut self, creatures: &mut CreatureMap, round: u32, config: &Config) {
+        let mut alive: Vec<usize> = Vec::new();
+        for (i, s) in self.species.iter_mut().enumerate() {
+            if s.extinction_round.is_none() {
+                s.size = 0;
+                alive.push(i);
+            }
+        }
+
+        // Many creatures share the exact same genome
+        let mut known: HashMap<Vec<Command>, usize> = HashMap::new();
+
+        for i in 0..creatures.get_num() {
+            let id = match creatures.get_creatureid_by_index(i) {
+                Some(id) => id,
+                None => continue,
+            };
+            let c = creatures.get_creature_mut(id).unwrap();
+
+            let index = match known.get(c.get_genes()) {
+                Some(index) => *index,
+                None => {
+                    let mut best: Option<(usize, usize)> = None;
+                    for &s in &alive {
+                        let dist = genome_distance(c.get_genes(), &self.species[s].representative);
+                        if dist <= config.species_threshold
+                            && best.map_or(true, |(_, best_dist)| dist < best_dist)
+                        {
+                            best = Some((s, dist));
+                        }
+                    }
+
+                    let index = match best {
+                        Some((s, _)) => s,
+                        None => {
+                            self.species.push(Species {
+                                id: self.species.len() as u32 + 1,
+                                representative: c.get_genes().clone(),
+                                size: 0,
+                                birth_round: round,
+                                extinction_round: None,
+                            });
+                            alive.push(self.species.len() - 1);
+                            self.species.len() - 1
+                        }
+                    };
+                    known.insert(c.get_genes().clone(), index);
+                    index
+                }
+            };
+
+            self.species[index].size += 1;
+            c.set_species(self.species[index].id);
+        }
+
+        for s in alive {
+            let s = &mut self.species[s];
+            if s.size == 0 {
+                s.extinction_round = Some(round);
+                s.representative.clear();
+            }
+        }
+    }
+}
+impl fmt::Display for SpeciesTracker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut alive: Vec<&Species> = self
+            .species
+            .iter()
+            .filter(|s| s.extinction_round.is_none())
+            .collect();
+        alive.sort_by_key(|s| std::cmp::Reverse(s.size));
+
+        writeln!(
+            f,
+            "==Species({} alive, {} extinct)==",
+            alive.len(),
+            self.species.len() - alive.len()
+        )?;
+        for s in alive {
+            writeln!(f, "{}", s)?;
+        }
+        write!(f, "============")
+    }
+}
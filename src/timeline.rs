@@ -0,0 +1,188 @@
+use crate::config::Config;
+use crate::creature::command::Command;
+use crate::creature::traits::Traits;
+use crate::creature::CreatureType;
+use crate::creaturemap::CreatureMap;
+use crate::events::Event;
+use crate::parsing::{self, arg};
+use crate::world::World;
+
+use rand::seq::SliceRandom;
+
+use std::fs;
+
+pub enum Intervention {
+    // Adds count creatures with the given genes on free tiles within radius of a point
+    Inject {
+        count: u32,
+        x: u32,
+        y: u32,
+        radius: u32,
+        genes: Vec<Command>,
+    },
+    // Changes a config parameter like a config file line would
+    Set(String, String),
+    RemoveCarnivores,
+    // Sets the food of every tile
+    Reseed(u32),
+    Event(Event),
+}
+impl Intervention {
+    // Parses "<action> [arguments]"
+    pub fn parse(args: &[&str]) -> Result<Intervention, String> {
+        if args.is_empty() {
+            return Err(String::from("missing action"));
+        }
+
+        let intervention = match args[0] {
+            "inject" => {
+                // The genes are the rest of the line so they can be separated by ", "
+                let genes = match args.get(5..) {
+                    Some(genes) if !genes.is_empty() => Command::parse_genes(&genes.join(" "))?,
+                    _ => return Err(String::from("missing genes for inject")),
+                };
+                Intervention::Inject {
+                    count: arg(args, 1)?,
+                    x: arg(args, 2)?,
+                    y: arg(args, 3)?,
+                    radius: arg(args, 4)?,
+                    genes,
+                }
+            }
+            "set" => {
+                if args.len() < 3 {
+                    return Err(String::from("expected set <key> <value>"));
+                }
+                let (key, value) = (args[1], args[2..].join(" "));
+                if Config::is_startup_only(key) {
+                    return Err(format!("{} can only be set at startup", key));
+                }
+                // Checks the value now rather than in the middle of the run
+                Config::default().set(key, &value)?;
+                Intervention::Set(key.to_string(), value)
+            }
+            "remove_carnivores" => Intervention::RemoveCarnivores,
+            "reseed" => Intervention::Reseed(arg(args, 1)?),
+            "event" => Intervention::Event(Event::parse(&args[1..])?),
+            _ => return Err(format!("unknown action '{}'", args[0])),
+        };
+
+        Ok(intervention)
+    }
+
+    // Returns a description of what happened for the log
+    pub fn apply(
+        &self,
+        world: &mut World,
+        creatures: &mut CreatureMap,
+        config: &mut Config,
+    ) -> String {
+        match self {
+            Intervention::Inject {
+                count,
+                x,
+                y,
+                radius,
+                genes,
+            } => {
+                let mut tiles: Vec<(u32, u32)> = world
+                    .get_tiles_in_radius(*x, *y, *radius)
+                    .into_iter()
+                    .filter(|(x, y)| !world.get_tile(*x, *y).is_blocked())
+                    .collect();
+                tiles.shuffle(&mut rand::thread_rng());
+                tiles.truncate(*count as usize);
+
+                for (x, y) in &tiles {
+                    let id =
+                        creatures.add_creature(*x, *y, genes.clone(), Traits::new(config), None);
+                    world.get_tile_mut(*x, *y).creature = Some(id);
+                }
+                format!(
+                    "Injected {}/{} creatures at ({}, {}) radius {}",
+                    tiles.len(),
+                    count,
+                    x,
+                    y,
+                    radius
+                )
+            }
+            Intervention::Set(key, value) => match config.set(key, value) {
+                Ok(_) => format!("Set {} to {}", key, value),
+                Err(e) => format!("Failed to set {}: {}", key, e),
+            },
+            Intervention::RemoveCarnivores => {
+                let mut removed = 0;
+                for i in 0..creatures.get_num() {
+                    let id = match creatures.get_creatureid_by_index(i) {
+                        Some(id) => id,
+                        None => continue,
+                    };
+                    let c = creatures.get_creature(id.clone()).unwrap();
+                    if let CreatureType::Carnivore = c.get_type() {
+                        let (x, y) = c.get_pos();
+                        world.get_tile_mut(x, y).creature = None;
+                        creatures.deallocate(id);
+                        removed += 1;
+                    }
+                }
+                format!("Removed {} carnivores", removed)
+            }
+            Intervention::Reseed(amount) => {
+                let (width, height) = world.get_size();
+                for y in 0..height {
+                    for x in 0..width {
+                        world.get_tile_mut(x, y).food = *amount;
+                    }
+                }
+                format!("Reseeded every tile with {} food", amount)
+            }
+            Intervention::Event(event) => event.apply(world, creatures),
+        }
+    }
+}
+
+// Interventions to run at given rounds during an experiment
+pub struct Timeline {
+    entries: Vec<(u32, Intervention)>,
+}
+impl Timeline {
+    pub fn new() -> Timeline {
+        Timeline {
+            entries: Vec::new(),
+        }
+    }
+
+    // Loads a file made of "<round> <action> [arguments]" lines, '#' starts a comment
+    // Interventions of the same round run in the order they're written
+    pub fn load(path: &str) -> Result<Timeline, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut timeline = Timeline::new();
+
+        for (num, line) in content.lines().enumerate() {
+            let line = parsing::strip_comment(line);
+
+            let args: Vec<&str> = line.split_whitespace().collect();
+            if args.is_empty() {
+                continue;
+            }
+
+            let round = args[0]
+                .parse::<u32>()
+                .map_err(|_| format!("{}:{}: invalid round '{}'", path, num + 1, args[0]))?;
+            let intervention = Intervention::parse(&args[1..])
+                .map_err(|e| format!("{}:{}: {}", path, num + 1, e))?;
+            timeline.entries.push((round, intervention));
+        }
+
+        Ok(timeline)
+    }
+
+    pub fn get_interventions(&self, round: u32) -> Vec<&Intervention> {
+        self.entries
+            .iter()
+            .filter(|(r, _)| *r == round)
+            .map(|(_, i)| i)
+            .collect()
+    }
+}
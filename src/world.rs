@@ -1,4 +1,10 @@
+use crate::config::{Config, WorldGenerator};
+use crate::creature::Egg;
 use crate::creaturemap::CreatureId;
+use crate::worldgen;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 use std::fmt;
 
@@ -6,33 +12,149 @@ pub struct World {
     width: u32,
     height: u32,
     map: Vec<Tile>,
+    // Scent layers creatures can emit into, they evaporate and spread every round
+    pheromones: Vec<Vec<f32>>,
+    // Offset added to every tile's temperature by the current season
+    season: f32,
+    // Weight of each tile when placing the initial creatures with the image spawn strategy
+    spawn_weights: Option<Vec<u8>>,
+}
+
+pub const NUM_TERRAINS: usize = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Terrain {
+    Grassland = 0,
+    Forest,
+    Desert,
+}
+impl Terrain {
+    pub fn all() -> [Terrain; NUM_TERRAINS] {
+        [Terrain::Grassland, Terrain::Forest, Terrain::Desert]
+    }
+
+    pub fn from_name(name: &str) -> Option<Terrain> {
+        Terrain::all()
+            .iter()
+            .find(|t| t.to_string().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+impl fmt::Display for Terrain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Terrain::Grassland => "Grassland",
+                Terrain::Forest => "Forest",
+                Terrain::Desert => "Desert",
+            }
+        )
+    }
 }
 
 #[derive(Clone)]
 pub struct Tile {
+    pub terrain: Terrain,
     pub food: u32,
+    // Second nutrient only creatures with some fibre digestion can feed on
+    pub fibre: u32,
+    // Energy left by dead creatures, slowly decays into food
+    pub carcass: u32,
     pub creature: Option<CreatureId>,
+    pub egg: Option<Box<Egg>>,
+    // Food added every round
+    pub regrowth: u32,
+    // Most food the tile can hold at the end of a round, in addition to its terrain's limit
+    pub capacity: Option<u32>,
+    // Walls can't be entered, eaten from or built upon
+    pub wall: bool,
+    // Temperature without the seasonal drift
+    pub temperature: f32,
+}
+impl Tile {
+    pub fn is_blocked(&self) -> bool {
+        self.creature.is_some() || self.wall
+    }
 }
 
 impl fmt::Display for Tile {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Food: {}", self.food)
+        write!(
+            f,
+            "Terrain: {} Food: {} Fibre: {} Carcass: {} Regrowth: {}",
+            self.terrain, self.food, self.fibre, self.carcass, self.regrowth
+        )?;
+        if let Some(capacity) = self.capacity {
+            write!(f, " Capacity: {}", capacity)?;
+        }
+        if self.wall {
+            write!(f, " Wall")?;
+        }
+        if let Some(egg) = &self.egg {
+            write!(f, "\n{}", egg)?;
+        }
+        Ok(())
     }
 }
 
 impl World {
-    pub fn new(width: u32, height: u32) -> World {
-        World {
+    pub fn new(config: &Config) -> World {
+        let (width, height) = (config.width, config.height);
+        let mut world = World {
             width: width,
             height: height,
             map: vec![
                 Tile {
+                    terrain: Terrain::Grassland,
                     food: 100,
+                    fibre: config.fibre_initial,
+                    carcass: 0,
                     creature: None,
+                    egg: None,
+                    regrowth: 0,
+                    capacity: None,
+                    wall: false,
+                    temperature: 0.0,
                 };
                 (width * height) as usize
             ],
+            pheromones: vec![vec![0.0; (width * height) as usize]; config.pheromone_layers],
+            season: 0.0,
+            spawn_weights: None,
+        };
+
+        // The temperature noise and the generator share the seed so it reproduces the whole world
+        let seed = match config.world_seed {
+            Some(seed) => seed,
+            None => rand::thread_rng().gen(),
+        };
+        let seeded = config.temperature_noise != 0.0
+            || !matches!(config.world_generator, WorldGenerator::Flat);
+        if seeded {
+            println!("Generating the world with seed {}", seed);
         }
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        // Goes from the pole temperature at the top and bottom edges to the equator one in the middle
+        let half = height as f32 / 2.0;
+        for y in 0..height {
+            let latitude = ((y as f32 + 0.5) - half).abs() / half;
+            let base = config.temperature_equator
+                + ((config.temperature_pole - config.temperature_equator) * latitude);
+            for x in 0..width {
+                let noise = if config.temperature_noise != 0.0 {
+                    rng.gen_range(-config.temperature_noise, config.temperature_noise)
+                } else {
+                    0.0
+                };
+                world.get_tile_mut(x, y).temperature = base + noise;
+            }
+        }
+
+        worldgen::generate(&mut world, config, &mut rng);
+        world
     }
 
     pub fn get_size(&self) -> (u32, u32) {
@@ -43,10 +165,96 @@ impl World {
         &self.map[((y * self.width) + x) as usize]
     }
 
+    // Coordinates out of the world wrap around like creature moves do
+    pub fn wrap_pos(&self, x: i64, y: i64) -> (u32, u32) {
+        (
+            x.rem_euclid(self.width as i64) as u32,
+            y.rem_euclid(self.height as i64) as u32,
+        )
+    }
+
+    pub fn get_tile_wrapped(&self, x: i64, y: i64) -> &Tile {
+        let (x, y) = self.wrap_pos(x, y);
+        self.get_tile(x, y)
+    }
+
+    // Every tile within radius of a point, each one only once even if the circle wraps onto itself
+    pub fn get_tiles_in_radius(&self, x: u32, y: u32, radius: u32) -> Vec<(u32, u32)> {
+        let r = radius as i64;
+        let mut tiles = Vec::new();
+
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if (dx * dx) + (dy * dy) <= r * r {
+                    tiles.push(self.wrap_pos(x as i64 + dx, y as i64 + dy));
+                }
+            }
+        }
+
+        tiles.sort();
+        tiles.dedup();
+        tiles
+    }
+
+    // Creatures within radius of a point, not counting the one on the point itself
+    pub fn count_neighbours(&self, x: u32, y: u32, radius: u32) -> u32 {
+        let r = radius as i64;
+        let mut count = 0;
+
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if (dx, dy) != (0, 0)
+                    && (dx * dx) + (dy * dy) <= r * r
+                    && self
+                        .get_tile_wrapped(x as i64 + dx, y as i64 + dy)
+                        .creature
+                        .is_some()
+                {
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    }
+
+    pub fn set_spawn_weights(&mut self, weights: Vec<u8>) {
+        self.spawn_weights = Some(weights);
+    }
+
+    pub fn get_spawn_weight(&self, x: u32, y: u32) -> Option<u8> {
+        let index = ((y * self.width) + x) as usize;
+        self.spawn_weights.as_ref().map(|weights| weights[index])
+    }
+
+    pub fn get_temperature(&self, x: u32, y: u32) -> f32 {
+        self.get_tile(x, y).temperature + self.season
+    }
+
     pub fn get_tile_mut(&mut self, x: u32, y: u32) -> &mut Tile {
         &mut self.map[((y * self.width) + x) as usize]
     }
 
+    // Creature on a tile unless it's the acting one, a world too narrow wraps its neighbours back onto it
+    pub fn get_other_creature(&self, x: u32, y: u32, own: &CreatureId) -> Option<CreatureId> {
+        match &self.get_tile(x, y).creature {
+            Some(id) if id != own => Some(id.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn get_num_pheromones(&self) -> usize {
+        self.pheromones.len()
+    }
+
+    pub fn get_pheromone(&self, layer: usize, x: u32, y: u32) -> f32 {
+        self.pheromones[layer][((y * self.width) + x) as usize]
+    }
+
+    pub fn add_pheromone(&mut self, layer: usize, x: u32, y: u32, amount: f32) {
+        self.pheromones[layer][((y * self.width) + x) as usize] += amount;
+    }
+
     pub fn get_num_creatures(&self) -> u32 {
         let mut nc = 0;
         for tile in &self.map {
@@ -56,4 +264,78 @@ impl World {
         }
         nc
     }
+
+    // Removes the eggs due to hatch whose tile isn't occupied by a creature
+    pub fn take_hatching_eggs(&mut self, round: u32) -> Vec<(u32, u32, Egg)> {
+        let mut eggs = Vec::new();
+
+        for (i, tile) in self.map.iter_mut().enumerate() {
+            let ready = match &tile.egg {
+                Some(egg) => egg.hatch_round <= round && tile.creature.is_none(),
+                None => false,
+            };
+            if ready {
+                let egg = tile.egg.take().unwrap();
+                let (x, y) = (i as u32 % self.width, i as u32 / self.width);
+                eggs.push((x, y, *egg));
+            }
+        }
+
+        eggs
+    }
+
+    // Environment changes that happen once per round
+    pub fn update(&mut self, config: &Config, round: u32) {
+        if config.season_length != 0 {
+            let phase = (round % config.season_length) as f32 / config.season_length as f32;
+            self.season = config.season_amplitude * (phase * 2.0 * std::f32::consts::PI).sin();
+        } else {
+            self.season = 0.0;
+        }
+
+        for tile in &mut self.map {
+            let decay = std::cmp::min(tile.carcass, config.carcass_decay);
+            tile.carcass -= decay;
+            tile.food += decay + tile.regrowth;
+            // Fibre grows back everywhere on its own up to a limit
+            if tile.fibre < config.fibre_max {
+                tile.fibre = std::cmp::min(tile.fibre + config.fibre_regrowth, config.fibre_max);
+            }
+
+            let terrain = &config.terrains[tile.terrain as usize];
+            if terrain.spoil_rate != 0 && tile.food > terrain.spoil_threshold {
+                // Rounded up so the food does reach the threshold
+                let excess = (tile.food - terrain.spoil_threshold) as u64;
+                tile.food -= (excess * terrain.spoil_rate as u64).div_ceil(100) as u32;
+            }
+            if terrain.max_food != 0 {
+                tile.food = std::cmp::min(tile.food, terrain.max_food);
+            }
+            if let Some(capacity) = tile.capacity {
+                tile.food = std::cmp::min(tile.food, capacity);
+            }
+        }
+
+        let evaporation = 1.0 - (config.pheromone_evaporation as f32 / 100.0);
+        let diffusion = config.pheromone_diffusion as f32 / 100.0;
+        let (width, height) = (self.width as usize, self.height as usize);
+
+        for layer in &mut self.pheromones {
+            // Each tile keeps part of its scent and gives the rest evenly to its 4 neighbours
+            let prev = layer.clone();
+            for y in 0..height {
+                let (up, down) = ((y + height - 1) % height, (y + 1) % height);
+                for x in 0..width {
+                    let (left, right) = ((x + width - 1) % width, (x + 1) % width);
+                    let neighbours = prev[(y * width) + left]
+                        + prev[(y * width) + right]
+                        + prev[(up * width) + x]
+                        + prev[(down * width) + x];
+                    let value = (prev[(y * width) + x] * (1.0 - diffusion))
+                        + (neighbours * diffusion / 4.0);
+                    layer[(y * width) + x] = value * evaporation;
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,132 @@
+use crate::config::{Config, WorldGenerator};
+use crate::world::{Terrain, World};
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+// Noise under which the islands generator leaves the land barren
+const SEA_LEVEL: f32 = 0.55;
+
+// Random values on a grid smoothly interpolated in between, wrapping around like the world
+struct ValueNoise {
+    cells_x: usize,
+    cells_y: usize,
+    scale: f32,
+    values: Vec<f32>,
+}
+impl ValueNoise {
+    fn new(rng: &mut StdRng, width: u32, height: u32, scale: u32) -> ValueNoise {
+        let scale = std::cmp::max(scale, 1);
+        let (cells_x, cells_y) = (
+            width.div_ceil(scale) as usize,
+            height.div_ceil(scale) as usize,
+        );
+
+        ValueNoise {
+            cells_x,
+            cells_y,
+            scale: scale as f32,
+            values: (0..(cells_x * cells_y)).map(|_| rng.gen::<f32>()).collect(),
+        }
+    }
+
+    fn get(&self, x: u32, y: u32) -> f32 {
+        let (fx, fy) = (x as f32 / self.scale, y as f32 / self.scale);
+        let (x0, y0) = (fx as usize, fy as usize);
+        let (x1, y1) = ((x0 + 1) % self.cells_x, (y0 + 1) % self.cells_y);
+
+        let smooth = |t: f32| t * t * (3.0 - (2.0 * t));
+        let (tx, ty) = (smooth(fx - x0 as f32), smooth(fy - y0 as f32));
+
+        let value = |cx: usize, cy: usize| self.values[(cy * self.cells_x) + cx];
+        let top = value(x0, y0) + ((value(x1, y0) - value(x0, y0)) * tx);
+        let bottom = value(x0, y1) + ((value(x1, y1) - value(x0, y1)) * tx);
+        top + ((bottom - top) * ty)
+    }
+}
+
+// Sums layers of noise of decreasing size and weight, between 0 and 1
+struct FractalNoise {
+    octaves: Vec<ValueNoise>,
+}
+impl FractalNoise {
+    fn new(rng: &mut StdRng, width: u32, height: u32, scale: u32) -> FractalNoise {
+        const NUM_OCTAVES: u32 = 3;
+        FractalNoise {
+            octaves: (0..NUM_OCTAVES)
+                .map(|i| ValueNoise::new(rng, width, height, scale >> i))
+                .collect(),
+        }
+    }
+
+    fn get(&self, x: u32, y: u32) -> f32 {
+        let (mut sum, mut weights, mut weight) = (0.0, 0.0, 1.0);
+        for octave in &self.octaves {
+            sum += octave.get(x, y) * weight;
+            weights += weight;
+            weight /= 2.0;
+        }
+        sum / weights
+    }
+}
+
+// Distance between two points going the shortest way around a world of that size
+fn wrapped_distance(
+    (width, height): (u32, u32),
+    (ax, ay): (u32, u32),
+    (bx, by): (u32, u32),
+) -> f32 {
+    let (dx, dy) = (ax.max(bx) - ax.min(bx), ay.max(by) - ay.min(by));
+    let (dx, dy) = (
+        std::cmp::min(dx, width - dx),
+        std::cmp::min(dy, height - dy),
+    );
+    ((dx * dx + dy * dy) as f32).sqrt()
+}
+
+// Fills the initial food, capacity, regrowth and terrain of every tile from a fertility between 0 and 1
+pub fn generate(world: &mut World, config: &Config, rng: &mut StdRng) {
+    if let WorldGenerator::Flat = config.world_generator {
+        return;
+    }
+
+    let (width, height) = world.get_size();
+    let scale = config.generator_scale;
+
+    let noise = FractalNoise::new(rng, width, height, scale);
+    let period = std::cmp::max(scale, 1) as f32;
+    let phase = rng.gen::<f32>();
+    let center = (rng.gen_range(0, width), rng.gen_range(0, height));
+
+    for y in 0..height {
+        for x in 0..width {
+            let f = match config.world_generator {
+                WorldGenerator::Flat => unreachable!(),
+                WorldGenerator::Noise => noise.get(x, y),
+                // Only the highest parts of the noise emerge from a barren sea
+                WorldGenerator::Islands => {
+                    ((noise.get(x, y) - SEA_LEVEL) / (1.0 - SEA_LEVEL)).max(0.0)
+                }
+                WorldGenerator::Stripes => {
+                    0.5 + (0.5 * (((x as f32 / period) + phase) * 2.0 * std::f32::consts::PI).sin())
+                }
+                WorldGenerator::Oasis => {
+                    (1.0 - (wrapped_distance((width, height), (x, y), center) / period)).max(0.0)
+                }
+            };
+
+            let tile = world.get_tile_mut(x, y);
+            tile.food = (f * config.generator_food as f32) as u32;
+            // Regrowth only brings a tile back to its initial food
+            tile.capacity = Some(tile.food);
+            tile.regrowth = (f * config.generator_regrowth as f32).round() as u32;
+            tile.terrain = if f < 0.25 {
+                Terrain::Desert
+            } else if f > 0.75 {
+                Terrain::Forest
+            } else {
+                Terrain::Grassland
+            };
+        }
+    }
+}